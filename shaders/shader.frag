@@ -1,7 +1,116 @@
 	#version 450
 
+	layout (set=0, binding=0) uniform Uniforms {
+	    mat4 model;
+	    mat4 view;
+	    mat4 projection;
+	    float time;
+	    float textureBlend;
+	    uint shading;
+	    mat3 normalMatrix;
+	    vec4 lightDirection;
+	    vec4 lightColour;
+	    vec4 ambientColour;
+	    vec4 headlightColour;
+	} ubo;
+
+	layout (set=0, binding=1) uniform sampler2D textureSampler;
+
+	layout (push_constant) uniform Material {
+	    vec4 ambient;
+	    vec4 diffuse;
+	    vec4 specular;
+	    uint illumination;
+	} material;
+
+	layout (location=0) in vec3 fragPosition;
+	layout (location=1) in vec2 fragTexcoord;
+	layout (location=2) in vec3 fragObjectPosition;
+	layout (location=3) in vec3 fragNormal;
+
 	layout (location=0) out vec4 theColour;
 
+	// values of `shading`, must match `Shading` in scene.rs
+	const uint SHADING_MATERIAL = 0u;
+	const uint SHADING_GREY = 1u;
+
+	uint hash(uint x) {
+	    x ^= x >> 16;
+	    x *= 0x7feb352du;
+	    x ^= x >> 15;
+	    x *= 0x846ca68bu;
+	    x ^= x >> 16;
+	    return x;
+	}
+
+	// a stable, well spread shade for every triangle
+	vec3 faceShade() {
+	#ifdef PRIMITIVE_ID
+	    uint face = uint(gl_PrimitiveID);
+	#else
+	    // without primitive ids faces are told apart by their quantized model space normal,
+	    // faces lying in the same plane share a shade
+	    vec3 normal = normalize(cross(dFdx(fragObjectPosition), dFdy(fragObjectPosition)));
+	    normal *= sign(dot(normal, vec3(1.0, 2.0, 3.0)));
+	    uvec3 quantized = uvec3((normal * 0.5 + 0.5) * 63.0 + 0.5);
+	    uint face = quantized.x | (quantized.y << 6) | (quantized.z << 12);
+	#endif
+	    uint key = hash(face);
+
+	    if (ubo.shading == SHADING_GREY) {
+	        return vec3(0.15 + 0.7 * float(key & 255u) / 255.0);
+	    }
+	    return vec3(uvec3(key, key >> 8, key >> 16) & 255u) / 255.0 * 0.7 + 0.15;
+	}
+
+	// diffuse and specular light reaching the eye from a light coming from `towards`
+	vec3 blinnPhong(vec3 normal, vec3 towards, vec3 eye, vec3 albedo, vec3 colour) {
+	    float diffuse = max(dot(normal, towards), 0.0);
+	    vec3 lit = albedo * diffuse;
+
+	    // illumination models below 2 have no highlights
+	    if (material.illumination >= 2u && diffuse > 0.0) {
+	        vec3 halfway = normalize(towards + eye);
+	        float shininess = max(material.specular.w, 1.0);
+	        lit += material.specular.rgb * pow(max(dot(normal, halfway), 0.0), shininess);
+	    }
+	    return lit * colour;
+	}
+
 	void main(){
-		theColour= vec4(1.0,0.0,0.0,1.0);
+	    vec3 eye = normalize(-fragPosition);
+
+	    // faces of a model with only some vn lines come without normal
+	    vec3 normal = dot(fragNormal, fragNormal) > 1e-8
+	        ? normalize(fragNormal)
+	        : normalize(cross(dFdx(fragPosition), dFdy(fragPosition)));
+
+	    // faces are not culled, the back of a face is lit like its front
+	    if (dot(normal, eye) < 0.0) {
+	        normal = -normal;
+	    }
+
+	    vec3 textureColour = texture(textureSampler, fragTexcoord).rgb;
+
+	    vec3 faceColour = ubo.shading == SHADING_MATERIAL ? material.diffuse.rgb : faceShade();
+	    vec3 albedo = mix(faceColour, textureColour, ubo.textureBlend);
+
+	    vec3 ambient = ubo.shading == SHADING_MATERIAL ? material.ambient.rgb : faceColour;
+	    ambient = mix(ambient, textureColour, ubo.textureBlend);
+
+	    vec3 colour = albedo;
+
+	    // illumination model 0 is a constant color
+	    if (material.illumination != 0u) {
+	        colour = ambient * ubo.ambientColour.rgb
+	            + blinnPhong(normal, ubo.lightDirection.xyz, eye, albedo, ubo.lightColour.rgb);
+
+	        if (ubo.headlightColour.w > 0.0) {
+	            float distance = length(fragPosition);
+	            float attenuation = 1.0 / (1.0 + 0.05 * distance * distance);
+	            colour += blinnPhong(normal, eye, eye, albedo, ubo.headlightColour.rgb) * attenuation;
+	        }
+	    }
+
+	    theColour = vec4(colour, material.diffuse.a);
 	}
@@ -1,6 +1,34 @@
 	#version 450
 
+	layout (set=0, binding=0) uniform Uniforms {
+	    mat4 model;
+	    mat4 view;
+	    mat4 projection;
+	    float time;
+	    float textureBlend;
+	    uint shading;
+	    mat3 normalMatrix;
+	    vec4 lightDirection;
+	    vec4 lightColour;
+	    vec4 ambientColour;
+	    vec4 headlightColour;
+	} ubo;
+
+	layout (location=0) in vec3 inPosition;
+	layout (location=1) in vec3 inNormal;
+	layout (location=2) in vec2 inTexcoord;
+
+	layout (location=0) out vec3 fragPosition;
+	layout (location=1) out vec2 fragTexcoord;
+	layout (location=2) out vec3 fragObjectPosition;
+	layout (location=3) out vec3 fragNormal;
+
 	void main() {
-	    gl_PointSize = 2.0;
-	    gl_Position = vec4(0.0,0.0,0.0,1.0);
+	    vec4 viewPosition = ubo.view * ubo.model * vec4(inPosition, 1.0);
+
+	    fragPosition = viewPosition.xyz;
+	    fragTexcoord = inTexcoord;
+	    fragObjectPosition = inPosition;
+	    fragNormal = ubo.normalMatrix * inNormal;
+	    gl_Position = ubo.projection * viewPosition;
 	}
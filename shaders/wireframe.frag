@@ -0,0 +1,8 @@
+	#version 450
+
+	layout (location=0) out vec4 theColour;
+
+	// edges drawn over the solid mesh, in a single color so they stand out from any shading
+	void main(){
+	    theColour = vec4(0.05, 0.05, 0.05, 1.0);
+	}
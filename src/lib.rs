@@ -0,0 +1,4 @@
+//! The viewer as a library, the binary and the offscreen renderer users share it
+mod scop;
+
+pub use crate::scop::*;
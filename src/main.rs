@@ -1,18 +1,80 @@
 use winit::event_loop::EventLoop;
 
-use crate::scop::Scop;
+use scop::Scop;
 
-mod scop;
+use scop::config::{Config, USAGE};
+
+use scop::loader::load_obj;
+
+use scop::log::{self, Subsystem};
+
+use scop::texture::{load_texture, Texture, Textures};
 
 fn main() {
-    let event_loop = EventLoop::new().unwrap();
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(Some(config)) => config,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = log::init(config.log_level, config.log_file.as_deref()) {
+        eprintln!("error: {err}");
+        std::process::exit(2);
+    }
+
+    let mut mesh = match load_obj(&config.model) {
+        Ok(mesh) => mesh,
+        Err(err) => {
+            log::error(Subsystem::Loader, err);
+            std::process::exit(1);
+        }
+    };
+
+    // a texture on the command line replaces the diffuse maps of every material
+    let textures = match config.texture.as_deref() {
+        Some(path) => load_texture(path).map(Textures::single),
+        None => Textures::load(&mesh.materials, Texture::checkerboard(256, 8)),
+    };
+
+    let textures = match textures {
+        Ok(textures) => textures,
+        Err(err) => {
+            log::error(Subsystem::Loader, err);
+            std::process::exit(1);
+        }
+    };
+
+    if !mesh.has_normals {
+        mesh.generate_normals(config.crease_angle);
+    }
+
+    if !mesh.has_texcoords {
+        mesh.generate_texcoords(config.uv_mapping);
+    }
+
+    let event_loop = match EventLoop::new() {
+        Ok(event_loop) => event_loop,
+        Err(err) => {
+            log::error(
+                Subsystem::App,
+                format_args!("failed to create the event loop: {err}"),
+            );
+            std::process::exit(1);
+        }
+    };
 
     event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
 
-    let mut scop = Scop::new();
+    let mut scop = Scop::new(config, mesh, textures);
 
     match event_loop.run_app(&mut scop) {
         Ok(_) => (),
-        Err(err) => eprintln!("error: {err}"),
+        Err(err) => log::error(Subsystem::App, err),
     }
 }
@@ -1,26 +1,135 @@
-use winit::event::WindowEvent::{CloseRequested, RedrawRequested};
+use winit::event::WindowEvent::{
+    CloseRequested, Focused, KeyboardInput, MouseInput, MouseWheel, RedrawRequested, Resized,
+};
+
+use winit::event::DeviceEvent;
+
+use winit::event_loop::ActiveEventLoop;
+
+use winit::keyboard::KeyCode;
+
+use std::path::PathBuf;
+
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use winit::dpi::PhysicalSize;
+
+use winit::window::{CursorGrabMode, Fullscreen};
 
 use winit::{application::ApplicationHandler, window::Window};
 
-use crate::scop::vulkan::Vulkan;
+use crate::scop::vulkan::{Capture, Vulkan};
+
+use crate::scop::loader::Mesh;
+
+use crate::scop::texture::{save_texture, Textures};
+
+use crate::scop::config::Config;
+
+use crate::scop::scene::{Scene, Shading};
+
+use crate::scop::animation::{Fade, Spin};
+
+use crate::scop::input::{Keyboard, Mouse};
+
+use crate::scop::camera::{Camera, CameraMode};
+
+use crate::scop::math::{Mat4, Vec3};
+
+use crate::scop::log::Subsystem;
 
 mod vulkan;
+pub use crate::scop::vulkan::Headless;
+
+pub mod animation;
+
+pub mod camera;
+
+pub mod config;
+
+pub mod input;
+
+pub mod loader;
+
+pub mod log;
+
+pub mod math;
+
+pub mod scene;
+
+pub mod texture;
+
+/// Units per second the object moves at while a direction key is held
+const MOVE_SPEED: f32 = 1.5;
 
-#[derive(Default)]
 pub struct Scop {
     window: Option<Window>,
     vulkan: Option<Vulkan>,
+    config: Config,
+    mesh: Mesh,
+    textures: Textures,
+    scene: Scene,
+    /// Centers the mesh on the origin and scales it to a unit box
+    placement: Mat4,
+    spin: Spin,
+    /// Cross-fade between the face colors and the texture
+    texturing: Fade,
+    keyboard: Keyboard,
+    mouse: Mouse,
+    camera: Camera,
+    /// Translation applied on top of the placement, moved with the keyboard
+    offset: Vec3,
+    start: Instant,
+    last_frame: Instant,
+    frames_drawn: u32,
+    /// The next presented frame is saved
+    capture_requested: bool,
 }
 
 impl ApplicationHandler for Scop {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         if self.window.is_none() {
-            let window = event_loop
-                .create_window(Window::default_attributes())
-                .expect("failed to create winit window !");
-            let vulkan_setup = Vulkan::new(&window).expect("failed to create vulkan setup");
+            let mut attributes = Window::default_attributes()
+                .with_title("scop")
+                .with_inner_size(PhysicalSize::new(self.config.width, self.config.height));
+
+            if self.config.fullscreen {
+                attributes = attributes.with_fullscreen(Some(Fullscreen::Borderless(None)));
+            }
+
+            let window = match event_loop.create_window(attributes) {
+                Ok(window) => window,
+                Err(err) => {
+                    log::error(
+                        Subsystem::App,
+                        format_args!("failed to create window: {err}"),
+                    );
+                    return event_loop.exit();
+                }
+            };
+
+            let vulkan_setup = match Vulkan::new(&window, &self.config, &self.mesh, &self.textures)
+            {
+                Ok(vulkan) => vulkan,
+                Err(err) => {
+                    log::error(
+                        Subsystem::Device,
+                        format_args!("failed to set up vulkan: {err}"),
+                    );
+                    return event_loop.exit();
+                }
+            };
+            if self.config.screenshot.is_some() && !vulkan_setup.can_capture() {
+                log::error(
+                    Subsystem::Swapchain,
+                    "the window surface does not allow screenshots",
+                );
+                return event_loop.exit();
+            }
+
             self.window = Some(window);
             self.vulkan = Some(vulkan_setup);
+            self.last_frame = Instant::now();
         }
     }
 
@@ -32,14 +141,98 @@ impl ApplicationHandler for Scop {
     ) {
         match event {
             RedrawRequested => {
-                self.vulkan.as_mut().unwrap().draw();
+                let now = Instant::now();
+
+                let delta = now.duration_since(self.last_frame).as_secs_f32();
+
+                self.last_frame = now;
+
+                self.spin.update(delta);
+                self.texturing.update(delta);
+                self.offset += self.movement() * (MOVE_SPEED * delta);
+
+                self.camera.pivot = self.offset;
+                self.camera.rotate(self.mouse.take_motion());
+                self.camera.zoom(self.mouse.take_scroll());
+                self.camera.update(delta, self.flight());
+
+                self.scene.time = now.duration_since(self.start).as_secs_f32();
+                self.scene.texture_blend = self.texturing.value;
+                self.scene.view = self.camera.view();
+                self.scene.model =
+                    Mat4::translation(self.offset) * self.spin.matrix() * self.placement;
+
+                // the command line screenshot is the last frame it asks for
+                self.frames_drawn += 1;
+                if self.config.screenshot.is_some() && self.frames_drawn >= self.config.frames {
+                    self.capture_requested = true;
+                }
+
+                let Some((vulkan, window)) = self.vulkan.as_mut().zip(self.window.as_ref()) else {
+                    return;
+                };
+
+                match vulkan.draw(window, &self.scene, self.capture_requested) {
+                    Ok(Some(captured)) => self.save_capture(event_loop, captured),
+                    Ok(None) => {}
+                    Err(err) => {
+                        log::error(
+                            Subsystem::Frame,
+                            format_args!("failed to draw frame: {err}"),
+                        );
+                        event_loop.exit();
+                    }
+                }
+            }
+
+            Resized(_) => {
+                if let Some(vulkan) = self.vulkan.as_mut() {
+                    vulkan.resized = true;
+                }
             }
 
+            KeyboardInput { event, .. } => {
+                if let Some(code) = self.keyboard.handle(&event) {
+                    self.key_pressed(event_loop, code);
+                }
+            }
+
+            MouseInput { state, button, .. } => self.mouse.handle_button(button, state),
+
+            MouseWheel { delta, .. } => self.mouse.handle_scroll(delta),
+
+            Focused(false) => {
+                self.keyboard.clear();
+                self.mouse.clear();
+                self.set_cursor_grab(false);
+            }
+
+            // the grab is given up with the focus, free-fly takes it back
+            Focused(true) if self.camera.mode == CameraMode::FreeFly => self.set_cursor_grab(true),
+
             CloseRequested => event_loop.exit(),
             _ => {}
         }
     }
 
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: winit::event::DeviceId,
+        event: DeviceEvent,
+    ) {
+        // raw deltas keep coming when the cursor is locked or outside the window
+        if let DeviceEvent::MouseMotion { delta } = event {
+            self.mouse.handle_motion(delta);
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
     fn exiting(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
         if let Some(vulkan) = self.vulkan.take() {
             drop(vulkan);
@@ -51,7 +244,148 @@ impl ApplicationHandler for Scop {
 }
 
 impl Scop {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(config: Config, mesh: Mesh, textures: Textures) -> Self {
+        let now = Instant::now();
+
+        // without materials every face would get the same default color
+        let shading = if mesh.materials.is_empty() {
+            Shading::Grey
+        } else {
+            Shading::Material
+        };
+
+        Self {
+            window: None,
+            vulkan: None,
+            config,
+            placement: mesh.bounds.fit(1.5),
+            mesh,
+            textures,
+            scene: Scene {
+                shading,
+                ..Scene::default()
+            },
+            spin: Spin::default(),
+            texturing: Fade::new(1.0),
+            keyboard: Keyboard::default(),
+            mouse: Mouse::default(),
+            camera: Camera::default(),
+            offset: Vec3::ZERO,
+            start: now,
+            last_frame: now,
+            frames_drawn: 0,
+            capture_requested: false,
+        }
+    }
+
+    fn key_pressed(&mut self, event_loop: &ActiveEventLoop, code: KeyCode) {
+        match code {
+            KeyCode::Escape => event_loop.exit(),
+            KeyCode::Space => self.spin.toggle_pause(),
+            KeyCode::KeyR => self.spin.reverse(),
+            KeyCode::KeyT => self.texturing.toggle(),
+            KeyCode::KeyC => self.scene.shading = self.scene.shading.next(),
+            KeyCode::F12 => match self.vulkan.as_ref() {
+                Some(vulkan) if vulkan.can_capture() => self.capture_requested = true,
+                _ => log::warning(
+                    Subsystem::Swapchain,
+                    "the window surface does not allow screenshots",
+                ),
+            },
+            KeyCode::KeyP => self.scene.draw_mode = self.scene.draw_mode.next(),
+            KeyCode::KeyL => self.scene.lighting.toggle_headlight(),
+            KeyCode::KeyF => {
+                self.camera.toggle_mode();
+                self.set_cursor_grab(self.camera.mode == CameraMode::FreeFly);
+            }
+            KeyCode::Equal | KeyCode::NumpadAdd => self.spin.faster(),
+            KeyCode::Minus | KeyCode::NumpadSubtract => self.spin.slower(),
+            _ => {}
+        }
+    }
+
+    /// Direction the held keys push the object in, not normalized
+    fn movement(&self) -> Vec3 {
+        Vec3::new(
+            self.keyboard.axis(KeyCode::ArrowLeft, KeyCode::ArrowRight),
+            self.keyboard.axis(KeyCode::ArrowDown, KeyCode::ArrowUp),
+            self.keyboard.axis(KeyCode::PageUp, KeyCode::PageDown),
+        )
+    }
+
+    /// Direction the held keys fly the camera in, as (right, up, forward)
+    fn flight(&self) -> Vec3 {
+        Vec3::new(
+            self.keyboard.axis(KeyCode::KeyA, KeyCode::KeyD),
+            self.keyboard.axis(KeyCode::KeyQ, KeyCode::KeyE),
+            self.keyboard.axis(KeyCode::KeyS, KeyCode::KeyW),
+        )
+    }
+
+    /// Hides and holds the cursor in the window, or gives it back
+    fn set_cursor_grab(&mut self, grab: bool) {
+        let Some(window) = self.window.as_ref() else {
+            return;
+        };
+
+        let result = if grab {
+            // not every platform can lock the cursor in place, keeping it inside works too
+            window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None)
+        };
+
+        if let Err(err) = result {
+            log::warning(
+                Subsystem::App,
+                format_args!("failed to grab the cursor: {err}"),
+            );
+        }
+        window.set_cursor_visible(!grab);
+        self.mouse.captured = grab;
+    }
+
+    /// Writes a captured frame where the command line asked, or to a new file for the key.
+    /// A failed capture only ends the program when it came from the command line
+    fn save_capture(&mut self, event_loop: &ActiveEventLoop, captured: Capture) {
+        self.capture_requested = false;
+
+        let image = match captured {
+            Ok(image) => image,
+            Err(err) => {
+                log::error(
+                    Subsystem::Frame,
+                    format_args!("failed to capture screenshot: {err}"),
+                );
+                if self.config.screenshot.is_some() {
+                    event_loop.exit();
+                }
+                return;
+            }
+        };
+
+        let path = self.config.screenshot.clone().unwrap_or_else(|| {
+            let since_epoch = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            PathBuf::from(format!("scop-{}.png", since_epoch.as_millis()))
+        });
+
+        match save_texture(&path, &image) {
+            Ok(()) => log::info(
+                Subsystem::Frame,
+                format_args!("screenshot saved to {}", path.display()),
+            ),
+            Err(err) => log::error(
+                Subsystem::Frame,
+                format_args!("failed to save screenshot: {err}"),
+            ),
+        }
+
+        if self.config.screenshot.is_some() {
+            event_loop.exit();
+        }
     }
 }
@@ -0,0 +1,97 @@
+use std::f32::consts::TAU;
+
+use crate::scop::math::Mat4;
+
+const DEFAULT_SPEED: f32 = 0.5;
+
+const SPEED_STEP: f32 = 1.25;
+
+const MIN_SPEED: f32 = 0.05;
+
+const MAX_SPEED: f32 = 8.0;
+
+/// Continuous rotation of the model around its vertical axis
+pub struct Spin {
+    /// Current angle in radians, kept in 0..TAU
+    pub angle: f32,
+    /// Radians per second, the direction comes from `reversed`
+    pub speed: f32,
+    pub reversed: bool,
+    pub paused: bool,
+}
+
+impl Default for Spin {
+    fn default() -> Self {
+        Self {
+            angle: 0.0,
+            speed: DEFAULT_SPEED,
+            reversed: false,
+            paused: false,
+        }
+    }
+}
+
+impl Spin {
+    /// Advances the angle by `delta` seconds worth of rotation
+    pub fn update(&mut self, delta: f32) {
+        if self.paused {
+            return;
+        }
+
+        let direction = if self.reversed { -1.0 } else { 1.0 };
+
+        self.angle = (self.angle + direction * self.speed * delta).rem_euclid(TAU);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn reverse(&mut self) {
+        self.reversed = !self.reversed;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * SPEED_STEP).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / SPEED_STEP).max(MIN_SPEED);
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::rotation_y(self.angle)
+    }
+}
+
+/// Value easing linearly toward 0 or 1, used to cross-fade between two looks
+pub struct Fade {
+    pub value: f32,
+    pub target: bool,
+    /// Seconds a full transition takes
+    pub duration: f32,
+}
+
+impl Fade {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            value: 0.0,
+            target: false,
+            duration,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.target = !self.target;
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        let step = delta / self.duration;
+
+        self.value = if self.target {
+            (self.value + step).min(1.0)
+        } else {
+            (self.value - step).max(0.0)
+        };
+    }
+}
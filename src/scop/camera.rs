@@ -0,0 +1,176 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::scop::math::{Mat4, Vec2, Vec3};
+
+/// Radians the camera turns per pixel the mouse travels
+const MOUSE_SENSITIVITY: f32 = 0.005;
+
+/// Pitch stays short of straight up or down, where the up vector of `look_at` degenerates
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// Units per second while flying
+const FLY_SPEED: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Looks at `target`, turns around `pivot` while dragging, the wheel moves closer or away
+    Orbit,
+    /// Moves with the keyboard and looks around with the mouse
+    FreeFly,
+}
+
+/// Both modes share the same orientation, switching mode keeps the view where it is
+pub struct Camera {
+    pub mode: CameraMode,
+    /// Point looked at in orbit, kept in the middle of the view until a drag turns around
+    /// `pivot`. Moving the model does not move it, so the model moves on screen
+    pub target: Vec3,
+    /// Point a drag turns around, the center of the model wherever it was moved
+    pub pivot: Vec3,
+    distance: f32,
+    /// Position of the eye, only followed in free-fly
+    position: Vec3,
+    /// Angle around the vertical axis, 0 looks down -z
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        let mut camera = Self {
+            mode: CameraMode::Orbit,
+            target: Vec3::ZERO,
+            pivot: Vec3::ZERO,
+            distance: 3.0,
+            position: Vec3::ZERO,
+            yaw: 0.0,
+            pitch: 0.0,
+        };
+        camera.position = camera.orbit_position();
+        camera
+    }
+}
+
+impl Camera {
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Orbit => {
+                self.position = self.orbit_position();
+                CameraMode::FreeFly
+            }
+            CameraMode::FreeFly => {
+                // orbit around whatever is in front, at the same distance it had before
+                self.target = self.position + self.forward() * self.distance;
+                CameraMode::Orbit
+            }
+        };
+    }
+
+    /// Turns the camera by a mouse movement in pixels, right and down are positive. In orbit
+    /// the eye travels around `pivot`, which stays where it is on screen
+    pub fn rotate(&mut self, motion: Vec2) {
+        let [right, up, forward] = self.axes();
+
+        let from_pivot = self.orbit_position() - self.pivot;
+
+        let local = Vec3::new(
+            from_pivot.dot(right),
+            from_pivot.dot(up),
+            from_pivot.dot(forward),
+        );
+
+        self.yaw -= motion.x * MOUSE_SENSITIVITY;
+        self.pitch = (self.pitch + motion.y * MOUSE_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+
+        if self.mode == CameraMode::Orbit {
+            let [right, up, forward] = self.axes();
+
+            let eye = self.pivot + right * local.x + up * local.y + forward * local.z;
+
+            self.target = eye + forward * self.distance;
+        }
+    }
+
+    /// Moves closer for positive wheel steps, only orbit zooms
+    pub fn zoom(&mut self, steps: f32) {
+        if self.mode == CameraMode::Orbit {
+            self.distance = (self.distance * 0.9f32.powf(steps)).clamp(0.5, 50.0);
+        }
+    }
+
+    /// Flies along `movement`, given as (right, up, forward) relative to where the camera looks
+    pub fn update(&mut self, delta: f32, movement: Vec3) {
+        if self.mode == CameraMode::FreeFly {
+            let [right, up, forward] = self.axes();
+
+            let direction = right * movement.x + up * movement.y + forward * movement.z;
+
+            self.position += direction.normalize() * (FLY_SPEED * delta);
+        }
+    }
+
+    pub fn view(&self) -> Mat4 {
+        let eye = match self.mode {
+            CameraMode::Orbit => self.orbit_position(),
+            CameraMode::FreeFly => self.position,
+        };
+        Mat4::look_at(eye, eye + self.forward(), Vec3::Y)
+    }
+
+    fn forward(&self) -> Vec3 {
+        -Vec3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        )
+    }
+
+    /// Right, up and forward directions of the view, the pitch never makes them degenerate
+    fn axes(&self) -> [Vec3; 3] {
+        let forward = self.forward();
+        let right = forward.cross(Vec3::Y).normalize();
+        [right, right.cross(forward), forward]
+    }
+
+    fn orbit_position(&self) -> Vec3 {
+        self.target - self.forward() * self.distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_vec3(actual: Vec3, expected: Vec3) {
+        assert!(
+            (actual - expected).length() < 1e-4,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn drag_keeps_the_pivot_in_place_on_screen() {
+        let mut camera = Camera {
+            pivot: Vec3::new(0.5, -0.25, 0.0),
+            ..Camera::default()
+        };
+
+        let seen = camera.view().transform_point(camera.pivot);
+
+        camera.rotate(Vec2::new(120.0, -40.0));
+
+        assert_vec3(camera.view().transform_point(camera.pivot), seen);
+        // the eye turned around the pivot at the same distance
+        assert!((camera.yaw + 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn drag_around_the_target_keeps_it() {
+        let mut camera = Camera::default();
+
+        camera.rotate(Vec2::new(-200.0, 80.0));
+
+        assert_vec3(camera.target, Vec3::ZERO);
+        assert_vec3(camera.orbit_position(), camera.forward() * -3.0);
+    }
+}
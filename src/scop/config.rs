@@ -0,0 +1,447 @@
+use std::path::PathBuf;
+
+use crate::scop::loader::UvMapping;
+
+use crate::scop::log::Level;
+
+pub const USAGE: &str = "usage: scop [options] <model.obj>
+
+options:
+    -t, --texture <path>    texture applied to every material instead of their map_Kd
+                            (.tga, .bmp, .ppm, .pgm)
+        --uv <mapping>      texture coordinates generated when the model has none,
+                            planar or spherical (default planar)
+        --crease-angle <degrees>
+                            normals generated when the model has none are smoothed
+                            across edges flatter than this, 0 gives flat faces (default 60)
+    -W, --width <pixels>    window width (default 1280)
+    -H, --height <pixels>   window height (default 720)
+    -f, --fullscreen        borderless fullscreen window
+        --vsync             wait for vertical blank when presenting
+        --validation        enable the vulkan validation layer if it is installed (default)
+        --no-validation     disable the vulkan validation layer
+        --validation-severity <level>
+                            least severe validation message shown, verbose, info,
+                            warning or error (default warning)
+        --validation-ignore <id>
+                            hide validation messages with this id name or number,
+                            can be repeated or given a comma separated list
+        --validation-panic  panic on the first validation error, implies --validation
+        --gpu <index>       physical device to use, in enumeration order
+        --screenshot <path> save the last of --frames frames to path and exit (.png, .ppm)
+        --frames <count>    frames rendered before --screenshot saves (default 1)
+        --log-level <level> least severe message shown, debug, info, warning or error
+                            (default info)
+        --log-file <path>   also append the messages shown to path
+    -h, --help              print this message
+
+environment:
+    SCOP_VALIDATION=0|1     default of --no-validation / --validation
+    SCOP_VALIDATION_SEVERITY, SCOP_VALIDATION_IGNORE, SCOP_VALIDATION_PANIC=1
+                            defaults of the matching options";
+
+/// Least severe message of the validation layer that is shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Verbose,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "verbose" => Ok(Self::Verbose),
+            "info" => Ok(Self::Info),
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            other => Err(format!("unknown validation severity '{other}'")),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Validation {
+    /// Asks for the layer, it is skipped with a warning when not installed
+    pub enabled: bool,
+    pub severity: Severity,
+    /// Message id names (e.g. `VUID-vkCmdDraw-None-02699`) or numbers never shown
+    pub ignored: Vec<String>,
+    /// Validation errors make the next frame panic, so tests fail on them
+    pub panic: bool,
+}
+
+impl Validation {
+    /// Defaults taken from the `SCOP_VALIDATION*` environment variables, the command line
+    /// overrides them
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// `from_env` with the variables looked up by `var`
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+
+        let mut validation = Self {
+            enabled: true,
+            severity: Severity::Warning,
+            ignored: Vec::new(),
+            panic: false,
+        };
+
+        if let Some(value) = var("SCOP_VALIDATION") {
+            validation.enabled = flag("SCOP_VALIDATION", &value)?;
+        }
+
+        if let Some(value) = var("SCOP_VALIDATION_SEVERITY") {
+            validation.severity = Severity::parse(&value)?;
+        }
+
+        if let Some(value) = var("SCOP_VALIDATION_IGNORE") {
+            validation.ignore(&value);
+        }
+
+        if let Some(value) = var("SCOP_VALIDATION_PANIC") {
+            validation.panic = flag("SCOP_VALIDATION_PANIC", &value)?;
+        }
+
+        Ok(validation)
+    }
+
+    fn ignore(&mut self, list: &str) {
+        self.ignored.extend(
+            list.split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(String::from),
+        );
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub model: PathBuf,
+    pub texture: Option<PathBuf>,
+    pub uv_mapping: UvMapping,
+    /// Radians
+    pub crease_angle: f32,
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub validation: Validation,
+    pub gpu: Option<usize>,
+    pub screenshot: Option<PathBuf>,
+    pub frames: u32,
+    pub log_level: Level,
+    pub log_file: Option<PathBuf>,
+}
+
+impl Config {
+    /// Parses the arguments following the program name, `Ok(None)` means help was asked
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Self>, String> {
+        Self::parse(args, Validation::from_env()?)
+    }
+
+    /// `from_args` starting from the given validation settings
+    fn parse<I: IntoIterator<Item = String>>(
+        args: I,
+        mut validation: Validation,
+    ) -> Result<Option<Self>, String> {
+        let mut model = None;
+        let mut texture = None;
+        let mut uv_mapping = UvMapping::Planar;
+        let mut crease_angle: f32 = 60.0;
+        let mut width = 1280;
+        let mut height = 720;
+        let mut fullscreen = false;
+        let mut vsync = false;
+        let mut gpu = None;
+        let mut screenshot = None;
+        let mut frames = 1;
+        let mut log_level = Level::Info;
+        let mut log_file = None;
+
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-t" | "--texture" => texture = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--uv" => {
+                    uv_mapping = match value(&arg, args.next())?.as_str() {
+                        "planar" => UvMapping::Planar,
+                        "spherical" => UvMapping::Spherical,
+                        other => return Err(format!("unknown uv mapping '{other}'")),
+                    }
+                }
+                "--crease-angle" => crease_angle = number(&arg, args.next())?,
+                "-W" | "--width" => width = number(&arg, args.next())?,
+                "-H" | "--height" => height = number(&arg, args.next())?,
+                "-f" | "--fullscreen" => fullscreen = true,
+                "--vsync" => vsync = true,
+                "--validation" => validation.enabled = true,
+                "--no-validation" => validation.enabled = false,
+                "--validation-severity" => {
+                    validation.severity = Severity::parse(&value(&arg, args.next())?)?
+                }
+                "--validation-ignore" => validation.ignore(&value(&arg, args.next())?),
+                "--validation-panic" => validation.panic = true,
+                "--gpu" => gpu = Some(number(&arg, args.next())?),
+                "--screenshot" => screenshot = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--frames" => frames = number(&arg, args.next())?,
+                "--log-level" => log_level = Level::parse(&value(&arg, args.next())?)?,
+                "--log-file" => log_file = Some(PathBuf::from(value(&arg, args.next())?)),
+                option if option.starts_with('-') && option.len() > 1 => {
+                    return Err(format!("unknown option '{option}'"));
+                }
+                path if model.is_none() => model = Some(PathBuf::from(path)),
+                path => return Err(format!("unexpected argument '{path}'")),
+            }
+        }
+
+        let model = model.ok_or("missing model path")?;
+
+        // panicking on errors means nothing without the layer reporting them
+        validation.enabled |= validation.panic;
+
+        if width == 0 || height == 0 {
+            return Err(String::from("window size must not be zero"));
+        }
+
+        if !(0.0..=180.0).contains(&crease_angle) {
+            return Err(String::from(
+                "crease angle must be between 0 and 180 degrees",
+            ));
+        }
+
+        if frames == 0 {
+            return Err(String::from("at least one frame must be rendered"));
+        }
+
+        // checked now rather than after rendering
+        if let Some(path) = screenshot.as_ref() {
+            let extension = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(str::to_ascii_lowercase);
+
+            if !matches!(extension.as_deref(), Some("png" | "ppm")) {
+                return Err(format!(
+                    "{}: screenshots are .png or .ppm files",
+                    path.display()
+                ));
+            }
+        }
+
+        Ok(Some(Self {
+            model,
+            texture,
+            uv_mapping,
+            crease_angle: crease_angle.to_radians(),
+            width,
+            height,
+            fullscreen,
+            vsync,
+            validation,
+            gpu,
+            screenshot,
+            frames,
+            log_level,
+            log_file,
+        }))
+    }
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("'{option}' expects a value"))
+}
+
+fn flag(name: &str, value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "on" | "true" | "yes" => Ok(true),
+        "0" | "off" | "false" | "no" => Ok(false),
+        _ => Err(format!("{name} expects 0 or 1, got '{value}'")),
+    }
+}
+
+fn number<T: std::str::FromStr>(option: &str, arg: Option<String>) -> Result<T, String> {
+    let value = value(option, arg)?;
+
+    value
+        .parse()
+        .map_err(|_| format!("'{option}' expects a positive number, got '{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> Validation {
+        Validation::from_vars(|_| None).expect("no variables is valid")
+    }
+
+    fn parse(args: &[&str]) -> Result<Option<Config>, String> {
+        Config::parse(args.iter().map(|arg| arg.to_string()), defaults())
+    }
+
+    fn config(args: &[&str]) -> Config {
+        parse(args)
+            .expect("valid arguments")
+            .expect("not asking for help")
+    }
+
+    #[test]
+    fn defaults_with_only_a_model() {
+        let config = config(&["cube.obj"]);
+
+        assert_eq!(config.model, PathBuf::from("cube.obj"));
+        assert_eq!(config.texture, None);
+        assert_eq!(config.uv_mapping, UvMapping::Planar);
+        assert!((config.crease_angle - 60f32.to_radians()).abs() < 1e-6);
+        assert_eq!((config.width, config.height), (1280, 720));
+        assert!(!config.fullscreen && !config.vsync);
+        assert!(config.validation.enabled);
+        assert_eq!(config.validation.severity, Severity::Warning);
+        assert_eq!(config.gpu, None);
+        assert_eq!(config.frames, 1);
+        assert_eq!(config.log_level, Level::Info);
+    }
+
+    #[test]
+    fn options_anywhere_around_the_model() {
+        let config = config(&[
+            "-W",
+            "640",
+            "cube.obj",
+            "--height",
+            "480",
+            "-t",
+            "wood.tga",
+            "--uv",
+            "spherical",
+            "--crease-angle",
+            "0",
+            "-f",
+            "--vsync",
+            "--gpu",
+            "1",
+            "--screenshot",
+            "shot.PNG",
+            "--frames",
+            "30",
+            "--log-level",
+            "debug",
+            "--log-file",
+            "scop.log",
+        ]);
+
+        assert_eq!((config.width, config.height), (640, 480));
+        assert_eq!(config.texture, Some(PathBuf::from("wood.tga")));
+        assert_eq!(config.uv_mapping, UvMapping::Spherical);
+        assert_eq!(config.crease_angle, 0.0);
+        assert!(config.fullscreen && config.vsync);
+        assert_eq!(config.gpu, Some(1));
+        assert_eq!(config.screenshot, Some(PathBuf::from("shot.PNG")));
+        assert_eq!(config.frames, 30);
+        assert_eq!(config.log_level, Level::Debug);
+        assert_eq!(config.log_file, Some(PathBuf::from("scop.log")));
+    }
+
+    #[test]
+    fn help_wins() {
+        assert!(parse(&["cube.obj", "--help"]).expect("valid").is_none());
+        assert!(parse(&["-h"]).expect("valid").is_none());
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let error = |args: &[&str]| parse(args).expect_err("invalid arguments");
+
+        assert_eq!(error(&[]), "missing model path");
+        assert_eq!(error(&["a.obj", "b.obj"]), "unexpected argument 'b.obj'");
+        assert_eq!(error(&["a.obj", "--fast"]), "unknown option '--fast'");
+        assert_eq!(error(&["a.obj", "--width"]), "'--width' expects a value");
+        assert_eq!(
+            error(&["a.obj", "--width", "-3"]),
+            "'--width' expects a positive number, got '-3'"
+        );
+        assert_eq!(error(&["a.obj", "-H", "0"]), "window size must not be zero");
+        assert_eq!(
+            error(&["a.obj", "--uv", "cubic"]),
+            "unknown uv mapping 'cubic'"
+        );
+        assert_eq!(
+            error(&["a.obj", "--crease-angle", "200"]),
+            "crease angle must be between 0 and 180 degrees"
+        );
+        assert_eq!(
+            error(&["a.obj", "--frames", "0"]),
+            "at least one frame must be rendered"
+        );
+        assert_eq!(
+            error(&["a.obj", "--screenshot", "shot.jpg"]),
+            "shot.jpg: screenshots are .png or .ppm files"
+        );
+        assert_eq!(
+            error(&["a.obj", "--log-level", "loud"]),
+            "unknown log level 'loud'"
+        );
+    }
+
+    #[test]
+    fn validation_options() {
+        let quiet = config(&[
+            "a.obj",
+            "--no-validation",
+            "--validation-severity",
+            "INFO",
+            "--validation-ignore",
+            "VUID-a, 0x1234",
+            "--validation-ignore",
+            "42",
+        ]);
+
+        assert!(!quiet.validation.enabled);
+        assert_eq!(quiet.validation.severity, Severity::Info);
+        assert_eq!(quiet.validation.ignored, ["VUID-a", "0x1234", "42"]);
+
+        // panicking on errors turns the layer back on
+        let panicking = config(&["a.obj", "--no-validation", "--validation-panic"]);
+        assert!(panicking.validation.enabled && panicking.validation.panic);
+    }
+
+    #[test]
+    fn validation_defaults_from_the_environment() {
+        let validation = Validation::from_vars(|name| {
+            match name {
+                "SCOP_VALIDATION" => Some("off"),
+                "SCOP_VALIDATION_SEVERITY" => Some("error"),
+                "SCOP_VALIDATION_IGNORE" => Some("a,,b"),
+                "SCOP_VALIDATION_PANIC" => Some(""),
+                _ => None,
+            }
+            .map(String::from)
+        })
+        .expect("valid variables");
+
+        assert!(!validation.enabled);
+        assert_eq!(validation.severity, Severity::Error);
+        assert_eq!(validation.ignored, ["a", "b"]);
+        // empty variables are unset
+        assert!(!validation.panic);
+
+        // the command line overrides them
+        let config = Config::parse(["a.obj", "--validation"].map(String::from), validation)
+            .expect("valid arguments")
+            .expect("not asking for help");
+        assert!(config.validation.enabled);
+
+        let error = Validation::from_vars(|name| {
+            (name == "SCOP_VALIDATION_PANIC").then(|| String::from("maybe"))
+        })
+        .expect_err("invalid flag");
+        assert_eq!(error, "SCOP_VALIDATION_PANIC expects 0 or 1, got 'maybe'");
+    }
+}
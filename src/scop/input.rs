@@ -0,0 +1,100 @@
+use std::collections::HashSet;
+
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta};
+
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::scop::math::Vec2;
+
+/// Pixels of a touchpad scroll counted as one wheel step
+const PIXELS_PER_STEP: f64 = 40.0;
+
+/// Tracks which keys are held, keyed on physical position so layouts do not matter
+#[derive(Default)]
+pub struct Keyboard {
+    held: HashSet<KeyCode>,
+}
+
+impl Keyboard {
+    /// Updates the held set, returns the key when it just went down (auto-repeat excluded)
+    pub fn handle(&mut self, event: &KeyEvent) -> Option<KeyCode> {
+        let PhysicalKey::Code(code) = event.physical_key else {
+            return None;
+        };
+
+        match event.state {
+            ElementState::Pressed => {
+                let fresh = self.held.insert(code);
+                (fresh && !event.repeat).then_some(code)
+            }
+            ElementState::Released => {
+                self.held.remove(&code);
+                None
+            }
+        }
+    }
+
+    pub fn is_held(&self, code: KeyCode) -> bool {
+        self.held.contains(&code)
+    }
+
+    /// -1, 0 or 1 depending on which of the two keys are held
+    pub fn axis(&self, negative: KeyCode, positive: KeyCode) -> f32 {
+        self.is_held(positive) as i32 as f32 - self.is_held(negative) as i32 as f32
+    }
+
+    /// Release events are lost while the window is unfocused
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+}
+
+/// Accumulates mouse input between two frames
+#[derive(Default)]
+pub struct Mouse {
+    /// Left button held
+    pub dragging: bool,
+    /// Cursor grabbed by the window, motion counts even without dragging
+    pub captured: bool,
+    motion: Vec2,
+    scroll: f32,
+}
+
+impl Mouse {
+    pub fn handle_button(&mut self, button: MouseButton, state: ElementState) {
+        if button == MouseButton::Left {
+            self.dragging = state == ElementState::Pressed;
+        }
+    }
+
+    /// Raw device motion, only kept while dragging or captured
+    pub fn handle_motion(&mut self, (x, y): (f64, f64)) {
+        if self.dragging || self.captured {
+            self.motion += Vec2::new(x as f32, y as f32);
+        }
+    }
+
+    pub fn handle_scroll(&mut self, delta: MouseScrollDelta) {
+        self.scroll += match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => (position.y / PIXELS_PER_STEP) as f32,
+        };
+    }
+
+    /// Motion in pixels since the last call, right and down are positive
+    pub fn take_motion(&mut self) -> Vec2 {
+        std::mem::replace(&mut self.motion, Vec2::ZERO)
+    }
+
+    /// Wheel steps since the last call, positive away from the user
+    pub fn take_scroll(&mut self) -> f32 {
+        std::mem::take(&mut self.scroll)
+    }
+
+    /// Button releases are lost while the window is unfocused
+    pub fn clear(&mut self) {
+        self.dragging = false;
+        self.motion = Vec2::ZERO;
+        self.scroll = 0.0;
+    }
+}
@@ -0,0 +1,338 @@
+mod obj;
+pub use crate::scop::loader::obj::load_obj;
+
+mod mtl;
+
+use std::collections::HashMap;
+
+use std::f32::consts::{PI, TAU};
+
+use std::path::PathBuf;
+
+use crate::scop::math::{Mat4, Vec3};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub texcoord: [f32; 2],
+}
+
+/// Range of indices drawn with a single material
+#[derive(Clone, Debug)]
+pub struct Group {
+    pub name: String,
+    pub first_index: u32,
+    pub index_count: u32,
+    /// Index into `Mesh::materials`, `None` uses `Material::default()`
+    pub material: Option<usize>,
+}
+
+/// Surface description from a .mtl library
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub name: String,
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub shininess: f32,
+    /// Opacity, 1 is fully opaque
+    pub dissolve: f32,
+    pub illumination: u32,
+    pub diffuse_map: Option<PathBuf>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: String::from("default"),
+            ambient: [0.2; 3],
+            diffuse: [0.8; 3],
+            specular: [0.0; 3],
+            shininess: 10.0,
+            dissolve: 1.0,
+            illumination: 1,
+            diffuse_map: None,
+        }
+    }
+}
+
+/// Axis aligned box around every vertex of a mesh
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Bounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Bounds {
+    pub fn from_vertices(vertices: &[Vertex]) -> Self {
+        let mut positions = vertices.iter().map(|vertex| Vec3::from(vertex.position));
+
+        let Some(first) = positions.next() else {
+            return Self::default();
+        };
+
+        positions.fold(
+            Self {
+                min: first,
+                max: first,
+            },
+            |bounds, position| Self {
+                min: bounds.min.min(position),
+                max: bounds.max.max(position),
+            },
+        )
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Moves the center of the box to the origin and scales its largest side to `extent`
+    pub fn fit(&self, extent: f32) -> Mat4 {
+        let size = self.size();
+
+        let largest = size.x.max(size.y).max(size.z);
+
+        let scale = if largest > 0.0 { extent / largest } else { 1.0 };
+
+        Mat4::scale(Vec3::splat(scale)) * Mat4::translation(-self.center())
+    }
+}
+
+/// How texture coordinates are made up for models without `vt` lines
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UvMapping {
+    /// Projects along the shortest side of the bounds
+    Planar,
+    /// Longitude and latitude around the center of the bounds
+    Spherical,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub groups: Vec<Group>,
+    pub materials: Vec<Material>,
+    pub has_normals: bool,
+    pub has_texcoords: bool,
+    pub bounds: Bounds,
+}
+
+impl Mesh {
+    /// Overwrites the texture coordinates of every vertex from its position
+    pub fn generate_texcoords(&mut self, mapping: UvMapping) {
+        let size = self.bounds.size();
+
+        let center = self.bounds.center();
+
+        // the two largest sides of the bounds span the projection plane
+        let (u_axis, v_axis) = if size.x <= size.y && size.x <= size.z {
+            (2, 1)
+        } else if size.y <= size.z {
+            (0, 2)
+        } else {
+            (0, 1)
+        };
+
+        let min = self.bounds.min.to_array();
+
+        let size = size
+            .to_array()
+            .map(|side| if side > 0.0 { side } else { 1.0 });
+
+        for vertex in self.vertices.iter_mut() {
+            vertex.texcoord = match mapping {
+                UvMapping::Planar => [
+                    (vertex.position[u_axis] - min[u_axis]) / size[u_axis],
+                    1.0 - (vertex.position[v_axis] - min[v_axis]) / size[v_axis],
+                ],
+                UvMapping::Spherical => {
+                    let direction = (Vec3::from(vertex.position) - center).normalize();
+                    [
+                        0.5 + direction.z.atan2(direction.x) / TAU,
+                        direction.y.clamp(-1.0, 1.0).acos() / PI,
+                    ]
+                }
+            };
+        }
+        self.has_texcoords = true;
+    }
+
+    /// Overwrites the normal of every vertex from the faces around it. Faces meeting at a
+    /// sharper angle than `crease_angle` (radians) keep a hard edge, 0 gives flat faces
+    pub fn generate_normals(&mut self, crease_angle: f32) {
+        let position = |index: u32| Vec3::from(self.vertices[index as usize].position);
+
+        let face_normals: Vec<Vec3> = self
+            .indices
+            .chunks_exact(3)
+            .map(|face| {
+                let [a, b, c] = [face[0], face[1], face[2]].map(position);
+                (b - a).cross(c - a).normalize()
+            })
+            .collect();
+
+        // corners are shared by position, vertices may have been split by their texcoords.
+        // Faces weigh by their angle at the corner so triangulating a polygon changes nothing
+        let mut faces_at: HashMap<[u32; 3], Vec<(usize, f32)>> = HashMap::new();
+
+        for (corner, index) in self.indices.iter().enumerate() {
+            let face = corner / 3;
+
+            let [previous, next] = [2, 1].map(|step| self.indices[face * 3 + (corner + step) % 3]);
+
+            let [to_previous, to_next] =
+                [previous, next].map(|other| (position(other) - position(*index)).normalize());
+
+            let angle = to_previous.dot(to_next).clamp(-1.0, 1.0).acos();
+
+            faces_at
+                .entry(self.vertices[*index as usize].position.map(f32::to_bits))
+                .or_default()
+                .push((face, angle));
+        }
+
+        let threshold = crease_angle.cos();
+
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+
+        let mut split: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+
+        for (corner, index) in self.indices.iter_mut().enumerate() {
+            let face_index = corner / 3;
+
+            let face = face_normals[face_index];
+
+            let mut vertex = self.vertices[*index as usize];
+
+            // the dot of a face with itself can round below cos(0) = 1, it is always kept
+            let normal = faces_at[&vertex.position.map(f32::to_bits)]
+                .iter()
+                .filter(|(other, _)| {
+                    *other == face_index || face_normals[*other].dot(face) >= threshold
+                })
+                .fold(Vec3::ZERO, |sum, (other, angle)| {
+                    sum + face_normals[*other] * *angle
+                })
+                .normalize();
+
+            vertex.normal = normal.to_array();
+
+            // a vertex on a hard edge gets one copy per side
+            *index = *split
+                .entry((*index, vertex.normal.map(f32::to_bits)))
+                .or_insert_with(|| {
+                    vertices.push(vertex);
+                    vertices.len() as u32 - 1
+                });
+        }
+        self.vertices = vertices;
+        self.has_normals = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    /// Eight shared corners turned off the axes, so face normals are not exact
+    fn cube() -> Mesh {
+        let rotation = Mat4::rotation_x(0.3) * Mat4::rotation_y(0.7);
+
+        let vertices = (0..8)
+            .map(|i| Vertex {
+                position: rotation
+                    .transform_point(Vec3::new(
+                        if i & 1 == 0 { -1.0 } else { 1.0 },
+                        if i & 2 == 0 { -1.0 } else { 1.0 },
+                        if i & 4 == 0 { -1.0 } else { 1.0 },
+                    ))
+                    .to_array(),
+                ..Vertex::default()
+            })
+            .collect::<Vec<_>>();
+
+        let quads = [
+            [0, 2, 6, 4],
+            [1, 3, 7, 5],
+            [0, 1, 5, 4],
+            [2, 3, 7, 6],
+            [0, 1, 3, 2],
+            [4, 5, 7, 6],
+        ];
+
+        let mut indices = Vec::new();
+
+        for mut quad in quads {
+            let [a, b, c] = [quad[0], quad[1], quad[2]].map(|i| Vec3::from(vertices[i].position));
+
+            // wind every face counter-clockwise seen from outside
+            if (b - a).cross(c - a).dot(a) < 0.0 {
+                quad.reverse();
+            }
+            indices
+                .extend([quad[0], quad[1], quad[2], quad[0], quad[2], quad[3]].map(|i| i as u32));
+        }
+
+        Mesh {
+            bounds: Bounds::from_vertices(&vertices),
+            vertices,
+            indices,
+            ..Mesh::default()
+        }
+    }
+
+    fn normal(mesh: &Mesh, index: u32) -> Vec3 {
+        Vec3::from(mesh.vertices[index as usize].normal)
+    }
+
+    #[test]
+    fn zero_crease_angle_gives_flat_faces() {
+        let mut mesh = cube();
+        mesh.generate_normals(0.0);
+
+        assert!(mesh.has_normals);
+        assert_eq!(mesh.vertices.len(), 24);
+
+        for face in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [face[0], face[1], face[2]]
+                .map(|index| Vec3::from(mesh.vertices[index as usize].position));
+
+            let expected = (b - a).cross(c - a).normalize();
+
+            for index in face {
+                let actual = normal(&mesh, *index);
+                assert!(
+                    (actual - expected).length() < EPSILON,
+                    "{actual:?} != {expected:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn straight_crease_angle_smooths_every_corner() {
+        let mut mesh = cube();
+        mesh.generate_normals(PI);
+
+        assert_eq!(mesh.vertices.len(), 8);
+
+        for vertex in mesh.vertices.iter() {
+            let actual = Vec3::from(vertex.normal);
+            let expected = Vec3::from(vertex.position).normalize();
+            assert!(
+                (actual - expected).length() < EPSILON,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+}
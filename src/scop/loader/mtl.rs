@@ -0,0 +1,164 @@
+use std::error::Error;
+
+use std::fs;
+
+use std::path::Path;
+
+use crate::scop::loader::Material;
+
+use crate::scop::loader::obj::parse_floats;
+
+/// Reads every material of a .mtl library, texture paths are resolved against its directory
+pub fn load_mtl(path: &Path) -> Result<Vec<Material>, Box<dyn Error>> {
+    let source = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    parse(&source, directory).map_err(|err| format!("{}: {err}", path.display()).into())
+}
+
+fn parse(source: &str, directory: &Path) -> Result<Vec<Material>, Box<dyn Error>> {
+    let mut materials: Vec<Material> = Vec::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+
+        let mut tokens = line.split_whitespace();
+
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        let arguments: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            materials.push(Material {
+                name: arguments.join(" "),
+                ..Material::default()
+            });
+            continue;
+        }
+
+        let Some(material) = materials.last_mut() else {
+            return Err(format!("line {}: '{keyword}' before any newmtl", number + 1).into());
+        };
+
+        material
+            .line(keyword, &arguments, directory)
+            .map_err(|err| format!("line {}: {err}", number + 1))?;
+    }
+    Ok(materials)
+}
+
+impl Material {
+    fn line(
+        &mut self,
+        keyword: &str,
+        arguments: &[&str],
+        directory: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        match keyword {
+            "Ka" => self.ambient = parse_color(arguments)?,
+            "Kd" => self.diffuse = parse_color(arguments)?,
+            "Ks" => self.specular = parse_color(arguments)?,
+            "Ns" => self.shininess = parse_floats::<1>(arguments)?[0],
+            "d" => self.dissolve = parse_floats::<1>(arguments)?[0],
+            // some exporters write transparency instead of dissolve
+            "Tr" => self.dissolve = 1.0 - parse_floats::<1>(arguments)?[0],
+            "illum" => {
+                let [illumination] = arguments else {
+                    return Err("illum expects one value".into());
+                };
+
+                self.illumination = illumination
+                    .parse()
+                    .map_err(|_| format!("invalid illumination model '{illumination}'"))?;
+            }
+            "map_Kd" => {
+                // options such as `-s 1 1 1` come first, the file name is last
+                let file = arguments.last().ok_or("map_Kd expects a file name")?;
+
+                self.diffuse_map = Some(directory.join(file));
+            }
+            // other maps and spectral colors are not used by the renderer
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// `r g b`, or a single value used for all three channels
+fn parse_color(arguments: &[&str]) -> Result<[f32; 3], Box<dyn Error>> {
+    match arguments.first() {
+        Some(&"spectral") | Some(&"xyz") => Err("only rgb colors are supported".into()),
+        _ if arguments.len() < 3 => {
+            let [value] = parse_floats::<1>(arguments)?;
+            Ok([value; 3])
+        }
+        _ => parse_floats(arguments),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    fn materials(source: &str) -> Vec<Material> {
+        parse(source, Path::new("models")).expect("valid mtl")
+    }
+
+    #[test]
+    fn reads_every_material() {
+        let materials = materials(
+            "# two materials\nnewmtl red\nKa 0.1 0 0\nKd 1 0 0\nKs 0.5\nNs 32\nd 0.5\nillum 2\n\
+             \nnewmtl blue paint\nKd 0 0 1\nTr 0.25\nmap_Kd -s 2 2 1 textures/blue.tga\n",
+        );
+
+        assert_eq!(materials.len(), 2);
+
+        let red = &materials[0];
+        assert_eq!(red.name, "red");
+        assert_eq!(red.ambient, [0.1, 0.0, 0.0]);
+        assert_eq!(red.diffuse, [1.0, 0.0, 0.0]);
+        assert_eq!(red.specular, [0.5; 3]);
+        assert_eq!(red.shininess, 32.0);
+        assert_eq!(red.dissolve, 0.5);
+        assert_eq!(red.illumination, 2);
+        assert_eq!(red.diffuse_map, None);
+
+        let blue = &materials[1];
+        assert_eq!(blue.name, "blue paint");
+        assert_eq!(blue.dissolve, 0.75);
+        // unset fields keep their defaults
+        assert_eq!(blue.ambient, Material::default().ambient);
+        assert_eq!(
+            blue.diffuse_map,
+            Some(PathBuf::from("models/textures/blue.tga"))
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error = |source: &str| {
+            parse(source, Path::new(""))
+                .expect_err("invalid mtl")
+                .to_string()
+        };
+
+        assert_eq!(error("Kd 1 1 1\n"), "line 1: 'Kd' before any newmtl");
+        assert_eq!(
+            error("newmtl a\nKd spectral red.spd\n"),
+            "line 2: only rgb colors are supported"
+        );
+        assert_eq!(
+            error("newmtl a\nillum high\n"),
+            "line 2: invalid illumination model 'high'"
+        );
+        assert_eq!(
+            error("newmtl a\nmap_Kd\n"),
+            "line 2: map_Kd expects a file name"
+        );
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+
+use std::error::Error;
+
+use std::fs;
+
+use std::path::Path;
+
+use crate::scop::loader::mtl::load_mtl;
+
+use crate::scop::loader::{Bounds, Group, Material, Mesh, Vertex};
+
+use crate::scop::log::{self, Subsystem};
+
+type Corner = (usize, Option<usize>, Option<usize>);
+
+struct Parser {
+    positions: Vec<[f32; 3]>,
+    texcoords: Vec<[f32; 2]>,
+    normals: Vec<[f32; 3]>,
+    corners: HashMap<Corner, u32>,
+    /// File names given to `mtllib`, relative to the .obj file
+    libraries: Vec<String>,
+    /// Index into `mesh.materials` of the last `usemtl`
+    material: Option<usize>,
+    mesh: Mesh,
+}
+
+pub fn load_obj(path: &Path) -> Result<Mesh, Box<dyn Error>> {
+    let source = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+
+    let (mut mesh, libraries) =
+        parse(&source).map_err(|err| format!("{}: {err}", path.display()))?;
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut available = Vec::new();
+
+    for library in libraries.iter().map(|library| directory.join(library)) {
+        // a missing library only costs the colors, the geometry is still usable
+        if !library.is_file() {
+            log::warning(
+                Subsystem::Loader,
+                format_args!("{}: material library not found", library.display()),
+            );
+            continue;
+        }
+        available.extend(load_mtl(&library)?);
+    }
+
+    // `usemtl` left placeholders carrying only the name
+    for material in mesh.materials.iter_mut() {
+        match available.iter().find(|found| found.name == material.name) {
+            Some(found) => *material = found.clone(),
+            None if !libraries.is_empty() => log::warning(
+                Subsystem::Loader,
+                format_args!(
+                    "{}: material '{}' not found in any library",
+                    path.display(),
+                    material.name
+                ),
+            ),
+            None => {}
+        }
+    }
+    Ok(mesh)
+}
+
+fn parse(source: &str) -> Result<(Mesh, Vec<String>), Box<dyn Error>> {
+    let mut parser = Parser {
+        positions: Vec::new(),
+        texcoords: Vec::new(),
+        normals: Vec::new(),
+        corners: HashMap::new(),
+        libraries: Vec::new(),
+        material: None,
+        mesh: Mesh::default(),
+    };
+
+    for (number, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+
+        let mut tokens = line.split_whitespace();
+
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        let arguments: Vec<&str> = tokens.collect();
+
+        parser
+            .line(keyword, &arguments)
+            .map_err(|err| format!("line {}: {err}", number + 1))?;
+    }
+
+    let mut mesh = std::mem::take(&mut parser.mesh);
+
+    if mesh.indices.is_empty() {
+        return Err("no faces found".into());
+    }
+
+    mesh.groups.retain(|group| group.index_count > 0);
+
+    mesh.bounds = Bounds::from_vertices(&mesh.vertices);
+
+    Ok((mesh, parser.libraries))
+}
+
+impl Parser {
+    fn line(&mut self, keyword: &str, arguments: &[&str]) -> Result<(), Box<dyn Error>> {
+        match keyword {
+            "v" => {
+                self.positions.push(parse_floats(arguments)?);
+            }
+            "vt" => {
+                let [u, v] = match arguments.len() {
+                    1 => [parse_floats::<1>(arguments)?[0], 0.0],
+                    _ => parse_floats(arguments)?,
+                };
+                // .obj puts v = 0 at the bottom of the image, vulkan at the top
+                self.texcoords.push([u, 1.0 - v]);
+            }
+            "vn" => {
+                self.normals.push(parse_floats(arguments)?);
+            }
+            "f" => self.face(arguments)?,
+            "o" | "g" => {
+                let name = arguments.join(" ");
+                let first_index = self.mesh.indices.len() as u32;
+
+                match self.mesh.groups.last_mut() {
+                    Some(group) if group.index_count == 0 => group.name = name,
+                    _ => self.mesh.groups.push(Group {
+                        name,
+                        first_index,
+                        index_count: 0,
+                        material: self.material,
+                    }),
+                }
+            }
+            "mtllib" => self
+                .libraries
+                .extend(arguments.iter().map(|file| file.to_string())),
+            "usemtl" => self.use_material(arguments.join(" ")),
+            // smoothing groups and curves are not handled here
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Switching material starts a new draw range within the current group
+    fn use_material(&mut self, name: String) {
+        let index = match self.mesh.materials.iter().position(|m| m.name == name) {
+            Some(index) => index,
+            None => {
+                self.mesh.materials.push(Material {
+                    name,
+                    ..Material::default()
+                });
+                self.mesh.materials.len() - 1
+            }
+        };
+
+        self.material = Some(index);
+
+        let first_index = self.mesh.indices.len() as u32;
+
+        match self.mesh.groups.last_mut() {
+            Some(group) if group.index_count == 0 => group.material = Some(index),
+            Some(group) => {
+                let name = group.name.clone();
+                self.mesh.groups.push(Group {
+                    name,
+                    first_index,
+                    index_count: 0,
+                    material: Some(index),
+                });
+            }
+            None => {}
+        }
+    }
+
+    fn face(&mut self, arguments: &[&str]) -> Result<(), Box<dyn Error>> {
+        if arguments.len() < 3 {
+            return Err(format!("face needs at least 3 vertices, got {}", arguments.len()).into());
+        }
+
+        let corners = arguments
+            .iter()
+            .map(|corner| self.corner(corner))
+            .collect::<Result<Vec<u32>, _>>()?;
+
+        if self.mesh.groups.is_empty() {
+            self.mesh.groups.push(Group {
+                name: String::from("default"),
+                first_index: self.mesh.indices.len() as u32,
+                index_count: 0,
+                material: self.material,
+            });
+        }
+
+        // fan triangulation, fine for the convex polygons .obj exporters produce
+        for i in 1..corners.len() - 1 {
+            self.mesh
+                .indices
+                .extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+        }
+
+        if let Some(group) = self.mesh.groups.last_mut() {
+            group.index_count += 3 * (corners.len() as u32 - 2);
+        }
+        Ok(())
+    }
+
+    fn corner(&mut self, corner: &str) -> Result<u32, Box<dyn Error>> {
+        let mut parts = corner.split('/');
+
+        let position = resolve_index(parts.next(), self.positions.len())?
+            .ok_or_else(|| format!("missing position index in '{corner}'"))?;
+
+        let texcoord = resolve_index(parts.next(), self.texcoords.len())?;
+
+        let normal = resolve_index(parts.next(), self.normals.len())?;
+
+        let key = (position, texcoord, normal);
+
+        if let Some(index) = self.corners.get(&key) {
+            return Ok(*index);
+        }
+
+        let vertex = Vertex {
+            position: self.positions[position],
+            normal: normal.map(|i| self.normals[i]).unwrap_or_default(),
+            texcoord: texcoord.map(|i| self.texcoords[i]).unwrap_or_default(),
+        };
+
+        self.mesh.has_normals |= normal.is_some();
+        self.mesh.has_texcoords |= texcoord.is_some();
+
+        let index = self.mesh.vertices.len() as u32;
+        self.mesh.vertices.push(vertex);
+        self.corners.insert(key, index);
+        Ok(index)
+    }
+}
+
+pub(super) fn parse_floats<const N: usize>(arguments: &[&str]) -> Result<[f32; N], Box<dyn Error>> {
+    if arguments.len() < N {
+        return Err(format!("expected {N} values, got {}", arguments.len()).into());
+    }
+
+    let mut values = [0.0; N];
+
+    for (value, argument) in values.iter_mut().zip(arguments) {
+        *value = argument
+            .parse()
+            .map_err(|_| format!("invalid number '{argument}'"))?;
+    }
+    Ok(values)
+}
+
+/// .obj indices start at 1, negative ones count back from the last element read
+fn resolve_index(index: Option<&str>, len: usize) -> Result<Option<usize>, Box<dyn Error>> {
+    let Some(index) = index.filter(|index| !index.is_empty()) else {
+        return Ok(None);
+    };
+
+    let value: i64 = index
+        .parse()
+        .map_err(|_| format!("invalid index '{index}'"))?;
+
+    let resolved = match value {
+        0 => None,
+        v if v > 0 => Some(v - 1),
+        v => Some(len as i64 + v),
+    };
+
+    match resolved {
+        Some(i) if i >= 0 && (i as usize) < len => Ok(Some(i as usize)),
+        _ => Err(format!("index {value} out of range").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::scop::math::Vec3;
+
+    fn mesh(source: &str) -> Mesh {
+        parse(source).expect("valid obj").0
+    }
+
+    fn error(source: &str) -> String {
+        parse(source).expect_err("invalid obj").to_string()
+    }
+
+    #[test]
+    fn quad_is_fan_triangulated() {
+        let mesh = mesh("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n");
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+        assert!(!mesh.has_normals);
+        assert!(!mesh.has_texcoords);
+        assert_eq!(mesh.bounds.max, Vec3::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn corners_are_shared_until_an_attribute_differs() {
+        let mesh = mesh(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvt 0.5 0.25\n\
+             f 1/1 2/2 3/3\nf 1/1 3/3 2/4\n",
+        );
+
+        // the second face reuses two corners, the third has another texcoord
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+        assert!(mesh.has_texcoords);
+        // v points up in .obj and down in vulkan
+        assert_eq!(mesh.vertices[3].texcoord, [0.5, 0.75]);
+    }
+
+    #[test]
+    fn negative_indices_count_back() {
+        let mesh = mesh("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf -3//-1 -2//-1 -1//-1\n");
+
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        assert_eq!(mesh.vertices[2].position, [0.0, 1.0, 0.0]);
+        assert_eq!(mesh.vertices[0].normal, [0.0, 0.0, 1.0]);
+        assert!(mesh.has_normals);
+    }
+
+    #[test]
+    fn comments_and_unknown_keywords_are_skipped() {
+        let mesh = mesh("# header\nv 0 0 0 # origin\nv 1 0 0\nv 0 1 0\ns off\nf 1 2 3\n");
+
+        assert_eq!(mesh.indices.len(), 3);
+    }
+
+    #[test]
+    fn materials_split_groups() {
+        let (mesh, libraries) = parse(
+            "mtllib a.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             o box\nusemtl red\nf 1 2 3\nusemtl blue\nf 1 3 2\nusemtl red\nf 2 1 3\n",
+        )
+        .expect("valid obj");
+
+        assert_eq!(libraries, ["a.mtl"]);
+
+        let names: Vec<_> = mesh.materials.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["red", "blue"]);
+
+        let groups: Vec<_> = mesh
+            .groups
+            .iter()
+            .map(|g| (g.name.as_str(), g.first_index, g.index_count, g.material))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("box", 0, 3, Some(0)),
+                ("box", 3, 3, Some(1)),
+                ("box", 6, 3, Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(error("v 0 0\n"), "line 1: expected 3 values, got 2");
+        assert_eq!(error("v 0 0 0\nv 0 x 0\n"), "line 2: invalid number 'x'");
+        assert_eq!(
+            error("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n"),
+            "line 4: index 4 out of range"
+        );
+        assert_eq!(
+            error("v 0 0 0\nf 1 1\n"),
+            "line 2: face needs at least 3 vertices, got 2"
+        );
+        assert_eq!(error("v 0 0 0\n"), "no faces found");
+    }
+}
@@ -0,0 +1,199 @@
+use std::error::Error;
+
+use std::fmt::Display;
+
+use std::fs::File;
+
+use std::io::Write;
+
+use std::path::Path;
+
+use std::sync::Mutex;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            other => Err(format!("unknown log level '{other}'")),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warning => "WARNING",
+            Self::Error => "ERROR",
+        }
+    }
+}
+
+/// Part of the program a message comes from, shown as a tag
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subsystem {
+    App,
+    Instance,
+    Validation,
+    Device,
+    Swapchain,
+    Loader,
+    Frame,
+}
+
+impl Subsystem {
+    fn tag(self) -> &'static str {
+        match self {
+            Self::App => "app",
+            Self::Instance => "instance",
+            Self::Validation => "validation",
+            Self::Device => "device",
+            Self::Swapchain => "swapchain",
+            Self::Loader => "loader",
+            Self::Frame => "frame",
+        }
+    }
+}
+
+struct Logger {
+    level: Level,
+    file: Option<File>,
+}
+
+/// Info and above on stderr until `init` says otherwise
+static LOGGER: Mutex<Logger> = Mutex::new(Logger {
+    level: Level::Info,
+    file: None,
+});
+
+/// Sets the least severe level shown and the file every shown message is also appended to
+pub fn init(level: Level, file: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let file = match file {
+        Some(path) => Some(
+            File::options()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|err| format!("{}: {err}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    let mut logger = LOGGER.lock().map_err(|_| "logger is poisoned")?;
+    logger.level = level;
+    logger.file = file;
+    Ok(())
+}
+
+pub fn log(level: Level, subsystem: Subsystem, message: impl Display) {
+    // a panic while logging must not silence everything after it
+    let mut logger = LOGGER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if level < logger.level {
+        return;
+    }
+
+    let line = format!(
+        "{} {:<7} [{}] {message}",
+        timestamp(),
+        level.name(),
+        subsystem.tag()
+    );
+
+    eprintln!("{line}");
+
+    // losing the file is reported once, then only stderr is written
+    if let Some(file) = logger.file.as_mut()
+        && let Err(err) = writeln!(file, "{line}")
+    {
+        logger.file = None;
+        eprintln!(
+            "{} ERROR   [app] failed to write the log file: {err}",
+            timestamp()
+        );
+    }
+}
+
+pub fn debug(subsystem: Subsystem, message: impl Display) {
+    log(Level::Debug, subsystem, message);
+}
+
+pub fn info(subsystem: Subsystem, message: impl Display) {
+    log(Level::Info, subsystem, message);
+}
+
+pub fn warning(subsystem: Subsystem, message: impl Display) {
+    log(Level::Warning, subsystem, message);
+}
+
+pub fn error(subsystem: Subsystem, message: impl Display) {
+    log(Level::Error, subsystem, message);
+}
+
+/// UTC time of day with milliseconds, enough to follow a session
+fn timestamp() -> String {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let seconds = since_epoch.as_secs() % 86400;
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_parse_in_any_case() {
+        assert_eq!(Level::parse("debug"), Ok(Level::Debug));
+        assert_eq!(Level::parse("Info"), Ok(Level::Info));
+        assert_eq!(Level::parse("WARNING"), Ok(Level::Warning));
+        assert_eq!(Level::parse("error"), Ok(Level::Error));
+        assert_eq!(
+            Level::parse("warn"),
+            Err(String::from("unknown log level 'warn'"))
+        );
+    }
+
+    #[test]
+    fn levels_are_ordered_by_severity() {
+        assert!(Level::Debug < Level::Info);
+        assert!(Level::Info < Level::Warning);
+        assert!(Level::Warning < Level::Error);
+    }
+
+    #[test]
+    fn timestamp_is_a_time_of_day() {
+        let timestamp = timestamp();
+
+        let bytes = timestamp.as_bytes();
+        assert_eq!(bytes.len(), 12, "{timestamp}");
+        assert_eq!([bytes[2], bytes[5], bytes[8]], [b':', b':', b'.']);
+        assert!(timestamp
+            .replace([':', '.'], "")
+            .bytes()
+            .all(|byte| byte.is_ascii_digit()));
+    }
+}
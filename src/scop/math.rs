@@ -0,0 +1,847 @@
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// Same size and alignment as a glsl `vec4` in std140
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec4 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+/// Column-major 3x3 matrix, use `to_std140` before writing it to a uniform buffer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat3 {
+    pub cols: [[f32; 3]; 3],
+}
+
+/// Column-major 4x4 matrix, laid out like a glsl `mat4` in std140
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4 {
+    pub cols: [[f32; 4]; 4],
+}
+
+/// Unit quaternion describing a rotation, `w` is the scalar part
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+macro_rules! vector {
+    ($name:ident, $($field:ident),+) => {
+        impl $name {
+            pub const ZERO: Self = Self { $($field: 0.0),+ };
+
+            pub const fn new($($field: f32),+) -> Self {
+                Self { $($field),+ }
+            }
+
+            pub fn splat(value: f32) -> Self {
+                Self { $($field: value),+ }
+            }
+
+            pub fn dot(self, other: Self) -> f32 {
+                0.0 $(+ self.$field * other.$field)+
+            }
+
+            pub fn length(self) -> f32 {
+                self.dot(self).sqrt()
+            }
+
+            /// Returns the vector unchanged when it has no length
+            pub fn normalize(self) -> Self {
+                let length = self.length();
+
+                if length == 0.0 {
+                    return self;
+                }
+                self / length
+            }
+
+            pub fn lerp(self, other: Self, t: f32) -> Self {
+                self + (other - self) * t
+            }
+
+            pub fn min(self, other: Self) -> Self {
+                Self { $($field: self.$field.min(other.$field)),+ }
+            }
+
+            pub fn max(self, other: Self) -> Self {
+                Self { $($field: self.$field.max(other.$field)),+ }
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Self { $($field: self.$field + other.$field),+ }
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, other: Self) {
+                *self = *self + other;
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Self { $($field: self.$field - other.$field),+ }
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, other: Self) {
+                *self = *self - other;
+            }
+        }
+
+        impl Mul<f32> for $name {
+            type Output = Self;
+
+            fn mul(self, scalar: f32) -> Self {
+                Self { $($field: self.$field * scalar),+ }
+            }
+        }
+
+        impl Div<f32> for $name {
+            type Output = Self;
+
+            fn div(self, scalar: f32) -> Self {
+                Self { $($field: self.$field / scalar),+ }
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+    };
+}
+
+vector!(Vec2, x, y);
+vector!(Vec3, x, y, z);
+vector!(Vec4, x, y, z, w);
+
+impl Vec3 {
+    pub const X: Self = Self::new(1.0, 0.0, 0.0);
+    pub const Y: Self = Self::new(0.0, 1.0, 0.0);
+    pub const Z: Self = Self::new(0.0, 0.0, 1.0);
+
+    pub fn cross(self, other: Self) -> Self {
+        Self::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn extend(self, w: f32) -> Vec4 {
+        Vec4::new(self.x, self.y, self.z, w)
+    }
+
+    pub fn to_array(self) -> [f32; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl From<[f32; 3]> for Vec3 {
+    fn from([x, y, z]: [f32; 3]) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl Vec4 {
+    pub fn truncate(self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+}
+
+impl Mat3 {
+    pub const IDENTITY: Self = Self {
+        cols: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+    };
+
+    /// Upper-left 3x3 block, the rotation and scale part of an affine transform
+    pub fn from_mat4(matrix: Mat4) -> Self {
+        let mut cols = [[0.0; 3]; 3];
+
+        for (c, col) in cols.iter_mut().enumerate() {
+            col.copy_from_slice(&matrix.cols[c][..3]);
+        }
+        Self { cols }
+    }
+
+    pub fn transpose(self) -> Self {
+        let mut cols = [[0.0; 3]; 3];
+
+        for (c, col) in cols.iter_mut().enumerate() {
+            for (r, value) in col.iter_mut().enumerate() {
+                *value = self.cols[r][c];
+            }
+        }
+        Self { cols }
+    }
+
+    pub fn determinant(self) -> f32 {
+        let [a, b, c] = self.cols.map(Vec3::from);
+        a.dot(b.cross(c))
+    }
+
+    /// `None` when the matrix is singular
+    pub fn inverse(self) -> Option<Self> {
+        let [a, b, c] = self.cols.map(Vec3::from);
+
+        let determinant = a.dot(b.cross(c));
+
+        // only a zero or subnormal determinant is singular, a small scale is still invertible
+        if !determinant.is_normal() {
+            return None;
+        }
+
+        // the rows of the inverse are the cross products of the columns
+        let rows = [b.cross(c), c.cross(a), a.cross(b)].map(|row| row / determinant);
+
+        Some(
+            Self {
+                cols: rows.map(Vec3::to_array),
+            }
+            .transpose(),
+        )
+    }
+
+    /// Matrix to transform normals with, given the transform applied to positions
+    pub fn normal_matrix(model: Mat4) -> Self {
+        Self::from_mat4(model)
+            .inverse()
+            .map(Self::transpose)
+            .unwrap_or(Self::IDENTITY)
+    }
+
+    /// glsl pads every column of a `mat3` to a `vec4` in std140
+    pub fn to_std140(self) -> [[f32; 4]; 3] {
+        self.cols.map(|[x, y, z]| [x, y, z, 0.0])
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut cols = [[0.0; 3]; 3];
+
+        for (c, col) in cols.iter_mut().enumerate() {
+            for (r, value) in col.iter_mut().enumerate() {
+                *value = (0..3).map(|k| self.cols[k][r] * other.cols[c][k]).sum();
+            }
+        }
+        Self { cols }
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, vector: Vec3) -> Vec3 {
+        let [a, b, c] = self.cols.map(Vec3::from);
+        a * vector.x + b * vector.y + c * vector.z
+    }
+}
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self {
+        cols: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut matrix = Self::IDENTITY;
+        matrix.cols[3] = [offset.x, offset.y, offset.z, 1.0];
+        matrix
+    }
+
+    pub fn scale(factors: Vec3) -> Self {
+        let mut matrix = Self::IDENTITY;
+        matrix.cols[0][0] = factors.x;
+        matrix.cols[1][1] = factors.y;
+        matrix.cols[2][2] = factors.z;
+        matrix
+    }
+
+    /// Counter-clockwise rotation when looking from +x toward the origin
+    pub fn rotation_x(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Self {
+            cols: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, cos, sin, 0.0],
+                [0.0, -sin, cos, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// Counter-clockwise rotation when looking from +y toward the origin
+    pub fn rotation_y(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Self {
+            cols: [
+                [cos, 0.0, -sin, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [sin, 0.0, cos, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// Counter-clockwise rotation when looking from +z toward the origin
+    pub fn rotation_z(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Self {
+            cols: [
+                [cos, sin, 0.0, 0.0],
+                [-sin, cos, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn from_quat(rotation: Quat) -> Self {
+        let Quat { x, y, z, w } = rotation;
+
+        Self {
+            cols: [
+                [
+                    1.0 - 2.0 * (y * y + z * z),
+                    2.0 * (x * y + w * z),
+                    2.0 * (x * z - w * y),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * y - w * z),
+                    1.0 - 2.0 * (x * x + z * z),
+                    2.0 * (y * z + w * x),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * z + w * y),
+                    2.0 * (y * z - w * x),
+                    1.0 - 2.0 * (x * x + y * y),
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// Right-handed perspective for vulkan clip space: y points down and depth goes 0..1
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let f = 1.0 / (fov_y / 2.0).tan();
+
+        Self {
+            cols: [
+                [f / aspect, 0.0, 0.0, 0.0],
+                [0.0, -f, 0.0, 0.0],
+                [0.0, 0.0, far / (near - far), -1.0],
+                [0.0, 0.0, near * far / (near - far), 0.0],
+            ],
+        }
+    }
+
+    /// Right-handed orthographic projection with the same conventions as `perspective`
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        Self {
+            cols: [
+                [2.0 / (right - left), 0.0, 0.0, 0.0],
+                [0.0, -2.0 / (top - bottom), 0.0, 0.0],
+                [0.0, 0.0, -1.0 / (far - near), 0.0],
+                [
+                    -(right + left) / (right - left),
+                    (top + bottom) / (top - bottom),
+                    -near / (far - near),
+                    1.0,
+                ],
+            ],
+        }
+    }
+
+    /// Right-handed view matrix, the camera looks down -z
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let f = (target - eye).normalize();
+        let s = f.cross(up).normalize();
+        let u = s.cross(f);
+
+        Self {
+            cols: [
+                [s.x, u.x, -f.x, 0.0],
+                [s.y, u.y, -f.y, 0.0],
+                [s.z, u.z, -f.z, 0.0],
+                [-s.dot(eye), -u.dot(eye), f.dot(eye), 1.0],
+            ],
+        }
+    }
+
+    pub fn transpose(self) -> Self {
+        let mut cols = [[0.0; 4]; 4];
+
+        for (c, col) in cols.iter_mut().enumerate() {
+            for (r, value) in col.iter_mut().enumerate() {
+                *value = self.cols[r][c];
+            }
+        }
+        Self { cols }
+    }
+
+    /// Pairs of 2x2 minors shared by `determinant` and `inverse`
+    fn minors(self) -> ([f32; 6], [f32; 6]) {
+        let m = self.cols;
+
+        let s = [
+            m[0][0] * m[1][1] - m[1][0] * m[0][1],
+            m[0][0] * m[1][2] - m[1][0] * m[0][2],
+            m[0][0] * m[1][3] - m[1][0] * m[0][3],
+            m[0][1] * m[1][2] - m[1][1] * m[0][2],
+            m[0][1] * m[1][3] - m[1][1] * m[0][3],
+            m[0][2] * m[1][3] - m[1][2] * m[0][3],
+        ];
+
+        let c = [
+            m[2][0] * m[3][1] - m[3][0] * m[2][1],
+            m[2][0] * m[3][2] - m[3][0] * m[2][2],
+            m[2][0] * m[3][3] - m[3][0] * m[2][3],
+            m[2][1] * m[3][2] - m[3][1] * m[2][2],
+            m[2][1] * m[3][3] - m[3][1] * m[2][3],
+            m[2][2] * m[3][3] - m[3][2] * m[2][3],
+        ];
+
+        (s, c)
+    }
+
+    pub fn determinant(self) -> f32 {
+        let (s, c) = self.minors();
+
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    /// `None` when the matrix is singular
+    pub fn inverse(self) -> Option<Self> {
+        let m = self.cols;
+
+        let (s, c) = self.minors();
+
+        let determinant =
+            s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+
+        // only a zero or subnormal determinant is singular, a small scale is still invertible
+        if !determinant.is_normal() {
+            return None;
+        }
+
+        let inverse = [
+            [
+                m[1][1] * c[5] - m[1][2] * c[4] + m[1][3] * c[3],
+                -m[0][1] * c[5] + m[0][2] * c[4] - m[0][3] * c[3],
+                m[3][1] * s[5] - m[3][2] * s[4] + m[3][3] * s[3],
+                -m[2][1] * s[5] + m[2][2] * s[4] - m[2][3] * s[3],
+            ],
+            [
+                -m[1][0] * c[5] + m[1][2] * c[2] - m[1][3] * c[1],
+                m[0][0] * c[5] - m[0][2] * c[2] + m[0][3] * c[1],
+                -m[3][0] * s[5] + m[3][2] * s[2] - m[3][3] * s[1],
+                m[2][0] * s[5] - m[2][2] * s[2] + m[2][3] * s[1],
+            ],
+            [
+                m[1][0] * c[4] - m[1][1] * c[2] + m[1][3] * c[0],
+                -m[0][0] * c[4] + m[0][1] * c[2] - m[0][3] * c[0],
+                m[3][0] * s[4] - m[3][1] * s[2] + m[3][3] * s[0],
+                -m[2][0] * s[4] + m[2][1] * s[2] - m[2][3] * s[0],
+            ],
+            [
+                -m[1][0] * c[3] + m[1][1] * c[1] - m[1][2] * c[0],
+                m[0][0] * c[3] - m[0][1] * c[1] + m[0][2] * c[0],
+                -m[3][0] * s[3] + m[3][1] * s[1] - m[3][2] * s[0],
+                m[2][0] * s[3] - m[2][1] * s[1] + m[2][2] * s[0],
+            ],
+        ];
+
+        Some(Self {
+            cols: inverse.map(|col| col.map(|value| value / determinant)),
+        })
+    }
+
+    pub fn transform_point(self, point: Vec3) -> Vec3 {
+        let result = self * point.extend(1.0);
+        result.truncate() / result.w
+    }
+
+    pub fn transform_vector(self, vector: Vec3) -> Vec3 {
+        (self * vector.extend(0.0)).truncate()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut cols = [[0.0; 4]; 4];
+
+        for (c, col) in cols.iter_mut().enumerate() {
+            for (r, value) in col.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.cols[k][r] * other.cols[c][k]).sum();
+            }
+        }
+        Self { cols }
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+
+    fn mul(self, vector: Vec4) -> Vec4 {
+        let [a, b, c, d] = self.cols.map(|[x, y, z, w]| Vec4::new(x, y, z, w));
+        a * vector.x + b * vector.y + c * vector.z + d * vector.w
+    }
+}
+
+impl Quat {
+    pub const IDENTITY: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+        w: 1.0,
+    };
+
+    /// Counter-clockwise rotation of `angle` radians around `axis`
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+
+        Self {
+            x: axis.x * sin,
+            y: axis.y * sin,
+            z: axis.z * sin,
+            w: cos,
+        }
+    }
+
+    pub fn normalize(self) -> Self {
+        let length = (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt();
+
+        Self {
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+            w: self.w / length,
+        }
+    }
+
+    pub fn conjugate(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: self.w,
+        }
+    }
+
+    pub fn rotate(self, vector: Vec3) -> Vec3 {
+        let axis = Vec3::new(self.x, self.y, self.z);
+        let t = axis.cross(vector) * 2.0;
+        vector + t * self.w + axis.cross(t)
+    }
+
+    /// Spherical interpolation along the shortest arc
+    pub fn slerp(self, other: Self, t: f32) -> Self {
+        let mut dot = self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w;
+
+        let other = if dot < 0.0 {
+            dot = -dot;
+            Self {
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+                w: -other.w,
+            }
+        } else {
+            other
+        };
+
+        let (a, b) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = dot.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+
+        Self {
+            x: self.x * a + other.x * b,
+            y: self.y * a + other.y * b,
+            z: self.z * a + other.z * b,
+            w: self.w * a + other.w * b,
+        }
+        .normalize()
+    }
+}
+
+impl Mul for Quat {
+    type Output = Self;
+
+    /// Applies `other` first, then `self`
+    fn mul(self, other: Self) -> Self {
+        Self {
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::f32::consts::FRAC_PI_2;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_vec3(actual: Vec3, expected: Vec3) {
+        assert!(
+            (actual - expected).length() < EPSILON,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    fn assert_mat4(actual: Mat4, expected: Mat4) {
+        for (a, e) in actual
+            .cols
+            .iter()
+            .flatten()
+            .zip(expected.cols.iter().flatten())
+        {
+            assert!((a - e).abs() < EPSILON, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn std140_layout() {
+        assert_eq!(size_of::<Mat4>(), 64);
+        assert_eq!(align_of::<Mat4>(), 16);
+        assert_eq!(size_of::<Vec4>(), 16);
+        assert_eq!(
+            Mat3::IDENTITY.to_std140(),
+            [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn cross_follows_right_hand_rule() {
+        assert_vec3(Vec3::X.cross(Vec3::Y), Vec3::Z);
+        assert_vec3(Vec3::Y.cross(Vec3::Z), Vec3::X);
+        assert_vec3(Vec3::Z.cross(Vec3::X), Vec3::Y);
+    }
+
+    #[test]
+    fn multiplication_applies_right_operand_first() {
+        let matrix = Mat4::translation(Vec3::new(1.0, 0.0, 0.0)) * Mat4::scale(Vec3::splat(2.0));
+
+        assert_vec3(
+            matrix.transform_point(Vec3::new(1.0, 1.0, 1.0)),
+            Vec3::new(3.0, 2.0, 2.0),
+        );
+    }
+
+    #[test]
+    fn rotations_are_counter_clockwise() {
+        assert_vec3(
+            Mat4::rotation_x(FRAC_PI_2).transform_vector(Vec3::Y),
+            Vec3::Z,
+        );
+        assert_vec3(
+            Mat4::rotation_y(FRAC_PI_2).transform_vector(Vec3::Z),
+            Vec3::X,
+        );
+        assert_vec3(
+            Mat4::rotation_z(FRAC_PI_2).transform_vector(Vec3::X),
+            Vec3::Y,
+        );
+    }
+
+    #[test]
+    fn perspective_maps_near_and_far_planes() {
+        let projection = Mat4::perspective(FRAC_PI_2, 2.0, 1.0, 10.0);
+
+        assert_vec3(
+            projection.transform_point(Vec3::new(0.0, 0.0, -1.0)),
+            Vec3::new(0.0, 0.0, 0.0),
+        );
+        assert_vec3(
+            projection.transform_point(Vec3::new(0.0, 0.0, -10.0)),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        // vulkan's y axis points down, the frustum top edge lands on -1
+        assert_vec3(
+            projection.transform_point(Vec3::new(2.0, 1.0, -1.0)),
+            Vec3::new(1.0, -1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn orthographic_maps_box_corners() {
+        let projection = Mat4::orthographic(-2.0, 2.0, -1.0, 1.0, 0.5, 5.0);
+
+        assert_vec3(
+            projection.transform_point(Vec3::new(-2.0, -1.0, -0.5)),
+            Vec3::new(-1.0, 1.0, 0.0),
+        );
+        assert_vec3(
+            projection.transform_point(Vec3::new(2.0, 1.0, -5.0)),
+            Vec3::new(1.0, -1.0, 1.0),
+        );
+    }
+
+    #[test]
+    fn look_at_moves_eye_to_origin() {
+        let view = Mat4::look_at(Vec3::new(0.0, 0.0, 3.0), Vec3::ZERO, Vec3::Y);
+
+        assert_mat4(view, Mat4::translation(Vec3::new(0.0, 0.0, -3.0)));
+
+        let view = Mat4::look_at(Vec3::new(4.0, 2.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Vec3::Y);
+
+        assert_vec3(view.transform_point(Vec3::new(4.0, 2.0, 0.0)), Vec3::ZERO);
+        assert_vec3(
+            view.transform_point(Vec3::new(0.0, 2.0, 0.0)),
+            Vec3::new(0.0, 0.0, -4.0),
+        );
+    }
+
+    #[test]
+    fn inverse_undoes_transform() {
+        let matrix = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotation_y(0.7)
+            * Mat4::rotation_x(-1.3)
+            * Mat4::scale(Vec3::new(2.0, 0.5, 3.0));
+
+        let inverse = matrix.inverse().unwrap();
+
+        assert_mat4(matrix * inverse, Mat4::IDENTITY);
+        assert_mat4(inverse * matrix, Mat4::IDENTITY);
+        assert!((matrix.determinant() - 3.0).abs() < EPSILON);
+
+        let matrix3 = Mat3::from_mat4(matrix);
+        let product = matrix3 * matrix3.inverse().unwrap();
+
+        for (a, e) in product
+            .cols
+            .iter()
+            .flatten()
+            .zip(Mat3::IDENTITY.cols.iter().flatten())
+        {
+            assert!((a - e).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        assert_eq!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+        assert_eq!(
+            Mat3::from_mat4(Mat4::scale(Vec3::new(0.0, 1.0, 1.0))).inverse(),
+            None
+        );
+    }
+
+    #[test]
+    fn small_scale_is_invertible() {
+        let scale = Mat4::scale(Vec3::splat(0.001));
+
+        let inverse = scale.inverse().expect("small scale has an inverse");
+
+        assert_mat4(scale * inverse, Mat4::IDENTITY);
+
+        // normals of a tiny model still follow its rotation
+        let rotation = Mat4::rotation_y(0.7);
+        let normal = Mat3::normal_matrix(rotation * scale) * Vec3::X;
+
+        assert_vec3(normal.normalize(), rotation.transform_vector(Vec3::X));
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let matrix = Mat4 {
+            cols: [
+                [1.0, 2.0, 3.0, 4.0],
+                [5.0, 6.0, 7.0, 8.0],
+                [9.0, 10.0, 11.0, 12.0],
+                [13.0, 14.0, 15.0, 16.0],
+            ],
+        };
+
+        assert_eq!(matrix.transpose().cols[0], [1.0, 5.0, 9.0, 13.0]);
+        assert_eq!(matrix.transpose().transpose(), matrix);
+    }
+
+    #[test]
+    fn quaternion_matches_matrix_rotation() {
+        let rotation = Quat::from_axis_angle(Vec3::Y, FRAC_PI_2);
+
+        assert_vec3(rotation.rotate(Vec3::X), Vec3::new(0.0, 0.0, -1.0));
+        assert_mat4(Mat4::from_quat(rotation), Mat4::rotation_y(FRAC_PI_2));
+
+        let combined = Quat::from_axis_angle(Vec3::X, 0.4) * Quat::from_axis_angle(Vec3::Z, 1.1);
+
+        assert_mat4(
+            Mat4::from_quat(combined),
+            Mat4::rotation_x(0.4) * Mat4::rotation_z(1.1),
+        );
+    }
+
+    #[test]
+    fn slerp_halves_the_angle() {
+        let half = Quat::IDENTITY.slerp(Quat::from_axis_angle(Vec3::Z, FRAC_PI_2), 0.5);
+
+        assert_mat4(Mat4::from_quat(half), Mat4::rotation_z(FRAC_PI_2 / 2.0));
+    }
+}
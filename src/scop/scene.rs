@@ -0,0 +1,109 @@
+use crate::scop::math::{Mat4, Vec3};
+
+/// Everything the renderer needs to know about a frame, rebuilt by the application every frame
+pub struct Scene {
+    pub clear_color: [f32; 4],
+    pub model: Mat4,
+    pub view: Mat4,
+    /// Seconds since the application started
+    pub time: f32,
+    /// 0 shows the face colors, 1 the texture, values in between mix both
+    pub texture_blend: f32,
+    pub shading: Shading,
+    pub lighting: Lighting,
+    pub draw_mode: DrawMode,
+}
+
+/// How the triangles of the mesh are rasterized
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DrawMode {
+    #[default]
+    Solid,
+    Wireframe,
+    Points,
+    /// Solid with the wireframe drawn on top
+    Overlay,
+}
+
+impl DrawMode {
+    pub fn next(self) -> Self {
+        match self {
+            DrawMode::Solid => DrawMode::Wireframe,
+            DrawMode::Wireframe => DrawMode::Points,
+            DrawMode::Points => DrawMode::Overlay,
+            DrawMode::Overlay => DrawMode::Solid,
+        }
+    }
+}
+
+/// Lights of the scene, colors are premultiplied by their intensity
+#[derive(Clone, Copy, Debug)]
+pub struct Lighting {
+    /// Direction the sun light travels in, world space
+    pub direction: Vec3,
+    pub color: Vec3,
+    pub ambient: Vec3,
+    /// Color of a point light sitting on the camera, `None` when it is off
+    pub headlight: Option<Vec3>,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::new(-0.4, -1.0, -0.6).normalize(),
+            color: Vec3::splat(0.9),
+            ambient: Vec3::splat(0.25),
+            headlight: None,
+        }
+    }
+}
+
+impl Lighting {
+    pub fn toggle_headlight(&mut self) {
+        self.headlight = match self.headlight {
+            Some(_) => None,
+            None => Some(Vec3::splat(0.6)),
+        };
+    }
+}
+
+/// How faces are colored before the texture is mixed in, must match `SHADING_*` in shader.frag
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shading {
+    /// Diffuse color of the material of each face
+    #[default]
+    Material = 0,
+    /// A different shade of grey for every face
+    Grey = 1,
+    /// A different color for every face
+    Palette = 2,
+}
+
+impl Shading {
+    pub fn next(self) -> Self {
+        match self {
+            Shading::Material => Shading::Grey,
+            Shading::Grey => Shading::Palette,
+            Shading::Palette => Shading::Material,
+        }
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self {
+            clear_color: [0.0, 0.0, 0.8, 1.0],
+            model: Mat4::IDENTITY,
+            view: Mat4::look_at(
+                Vec3::new(0.0, 0.0, 3.0),
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ),
+            time: 0.0,
+            texture_blend: 0.0,
+            shading: Shading::default(),
+            lighting: Lighting::default(),
+            draw_mode: DrawMode::default(),
+        }
+    }
+}
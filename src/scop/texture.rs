@@ -0,0 +1,190 @@
+mod bmp;
+
+mod png;
+
+mod pnm;
+
+mod tga;
+
+use std::error::Error;
+
+use std::fs;
+
+use std::path::{Path, PathBuf};
+
+use crate::scop::loader::Material;
+
+/// Decoded image, 4 bytes per pixel in RGBA order with the first row at the top
+#[derive(Clone, Debug)]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Texture {
+    /// Grey and white checkers, shown when no texture was given on the command line
+    pub fn checkerboard(size: u32, squares: u32) -> Self {
+        let square = (size / squares).max(1);
+
+        let pixels = (0..size * size)
+            .flat_map(|i| {
+                let (x, y) = (i % size / square, i / size / square);
+                let value = if (x + y) % 2 == 0 { 0xff } else { 0x80 };
+                [value, value, value, 0xff]
+            })
+            .collect();
+
+        Self {
+            width: size,
+            height: size,
+            pixels,
+        }
+    }
+}
+
+/// Every texture a model samples, the first one is used by materials without a diffuse map
+pub struct Textures {
+    pub images: Vec<Texture>,
+    /// Index into `images` for each mesh material, missing ones use the first image
+    pub materials: Vec<usize>,
+}
+
+impl Textures {
+    /// One texture sampled by every material
+    pub fn single(texture: Texture) -> Self {
+        Self {
+            images: vec![texture],
+            materials: Vec::new(),
+        }
+    }
+
+    /// Loads the diffuse map of every material once, however many materials share it
+    pub fn load(materials: &[Material], fallback: Texture) -> Result<Self, Box<dyn Error>> {
+        Self::gather(materials, fallback, load_texture)
+    }
+
+    fn gather(
+        materials: &[Material],
+        fallback: Texture,
+        mut load: impl FnMut(&Path) -> Result<Texture, Box<dyn Error>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut images = vec![fallback];
+
+        let mut paths: Vec<&PathBuf> = Vec::new();
+
+        let mut indices = Vec::with_capacity(materials.len());
+
+        for material in materials.iter() {
+            let index = match &material.diffuse_map {
+                None => 0,
+                Some(path) => match paths.iter().position(|known| *known == path) {
+                    Some(found) => found + 1,
+                    None => {
+                        images.push(load(path)?);
+                        paths.push(path);
+                        paths.len()
+                    }
+                },
+            };
+            indices.push(index);
+        }
+
+        Ok(Self {
+            images,
+            materials: indices,
+        })
+    }
+
+    /// Index into `images` of the texture sampled by a group drawn with `material`
+    pub fn index(&self, material: Option<usize>) -> usize {
+        material
+            .and_then(|material| self.materials.get(material).copied())
+            .unwrap_or(0)
+    }
+}
+
+/// Decodes a TGA, BMP, PPM or PGM file, told apart by their magic bytes (or the extension
+/// for TGA, which has none)
+pub fn load_texture(path: &Path) -> Result<Texture, Box<dyn Error>> {
+    let data = fs::read(path).map_err(|err| format!("{}: {err}", path.display()))?;
+
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+
+    let texture = match (data.get(..2), extension.as_deref()) {
+        (Some(b"BM"), _) => bmp::decode(&data),
+        (Some([b'P', b'1'..=b'7']), _) => pnm::decode(&data),
+        (_, Some("tga")) => tga::decode(&data),
+        _ => Err("unsupported texture format, expected tga, bmp, ppm or pgm".into()),
+    };
+
+    texture.map_err(|err| format!("{}: {err}", path.display()).into())
+}
+
+/// Encodes a PNG or PPM file depending on the extension of `path`
+pub fn save_texture(path: &Path, texture: &Texture) -> Result<(), Box<dyn Error>> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+
+    let data = match extension.as_deref() {
+        Some("png") => png::encode(texture),
+        Some("ppm") => pnm::encode(texture),
+        _ => return Err(format!("{}: expected a .png or .ppm file", path.display()).into()),
+    };
+
+    fs::write(path, data).map_err(|err| format!("{}: {err}", path.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn material(diffuse_map: Option<&str>) -> Material {
+        Material {
+            diffuse_map: diffuse_map.map(PathBuf::from),
+            ..Material::default()
+        }
+    }
+
+    #[test]
+    fn shared_maps_are_loaded_once() {
+        let materials = [
+            material(Some("wood.tga")),
+            material(None),
+            material(Some("stone.tga")),
+            material(Some("wood.tga")),
+        ];
+
+        let mut loaded = Vec::new();
+
+        let textures = Textures::gather(&materials, Texture::checkerboard(2, 2), |path| {
+            loaded.push(path.to_path_buf());
+            Ok(Texture::checkerboard(1, 1))
+        })
+        .expect("every map loads");
+
+        assert_eq!(
+            loaded,
+            [PathBuf::from("wood.tga"), PathBuf::from("stone.tga")]
+        );
+        assert_eq!(textures.images.len(), 3);
+        assert_eq!(textures.materials, [1, 0, 2, 1]);
+        assert_eq!(textures.index(Some(2)), 2);
+        // groups without material sample the fallback
+        assert_eq!(textures.index(None), 0);
+    }
+
+    #[test]
+    fn single_texture_is_sampled_by_every_material() {
+        let textures = Textures::single(Texture::checkerboard(2, 2));
+
+        assert_eq!(textures.images.len(), 1);
+        assert_eq!(textures.index(Some(3)), 0);
+        assert_eq!(textures.index(None), 0);
+    }
+}
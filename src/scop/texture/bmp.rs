@@ -0,0 +1,210 @@
+use std::error::Error;
+
+use crate::scop::texture::Texture;
+
+const FILE_HEADER_SIZE: usize = 14;
+
+/// Smallest info header with the fields read here, `BITMAPINFOHEADER`
+const INFO_HEADER_SIZE: usize = 40;
+
+const BI_RGB: u32 = 0;
+
+const BI_BITFIELDS: u32 = 3;
+
+/// Windows bitmap, uncompressed 24 bit or 32 bit (plain or with channel masks)
+pub fn decode(data: &[u8]) -> Result<Texture, Box<dyn Error>> {
+    if data.len() < FILE_HEADER_SIZE + INFO_HEADER_SIZE || &data[..2] != b"BM" {
+        return Err("not a bmp file".into());
+    }
+
+    const TRUNCATED: &str = "truncated header";
+
+    let pixel_offset = u32_at(data, 10).ok_or(TRUNCATED)? as usize;
+    let header_size = u32_at(data, 14).ok_or(TRUNCATED)? as usize;
+
+    if header_size < INFO_HEADER_SIZE {
+        return Err("OS/2 bitmap headers are not supported".into());
+    }
+
+    let width = u32_at(data, 18).ok_or(TRUNCATED)? as i32;
+    let height = u32_at(data, 22).ok_or(TRUNCATED)? as i32;
+    let depth = u16_at(data, 28).ok_or(TRUNCATED)?;
+    let compression = u32_at(data, 30).ok_or(TRUNCATED)?;
+
+    if width <= 0 || height == 0 {
+        return Err(format!("invalid size {width}x{height}").into());
+    }
+
+    // a negative height means the rows are stored top to bottom
+    let top_to_bottom = height < 0;
+
+    let (width, height) = (width as u32, height.unsigned_abs());
+
+    let masks = match (compression, depth) {
+        (BI_RGB, 24) => None,
+        (BI_RGB, 32) => Some([0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0]),
+        (BI_BITFIELDS, 32) => {
+            let mask = |offset: usize| u32_at(data, offset).ok_or("truncated channel masks");
+
+            // the alpha mask is only part of the header from version 3 on
+            let alpha = if header_size >= 56 { mask(66)? } else { 0 };
+
+            Some([mask(54)?, mask(58)?, mask(62)?, alpha])
+        }
+        (BI_RGB | BI_BITFIELDS, depth) => {
+            return Err(format!("unsupported bit depth {depth}, expected 24 or 32").into());
+        }
+        (compression, _) => {
+            return Err(format!("unsupported compression method {compression}").into());
+        }
+    };
+
+    let bytes_per_pixel = depth as usize / 8;
+
+    // rows are padded to a multiple of 4 bytes
+    let stride = (width as usize * bytes_per_pixel).next_multiple_of(4);
+
+    let end = stride
+        .checked_mul(height as usize)
+        .and_then(|size| size.checked_add(pixel_offset))
+        .ok_or("image too large")?;
+
+    let body = data.get(pixel_offset..end).ok_or("truncated pixel data")?;
+
+    let mut rows: Vec<&[u8]> = body.chunks_exact(stride).collect();
+
+    if !top_to_bottom {
+        rows.reverse();
+    }
+
+    let pixels = rows
+        .into_iter()
+        .flat_map(|row| row[..width as usize * bytes_per_pixel].chunks_exact(bytes_per_pixel))
+        .flat_map(|pixel| match (pixel, masks) {
+            ([b, g, r], _) => [*r, *g, *b, 0xff],
+            (_, Some([red, green, blue, alpha])) => {
+                let value = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                [
+                    channel(value, red),
+                    channel(value, green),
+                    channel(value, blue),
+                    if alpha == 0 {
+                        0xff
+                    } else {
+                        channel(value, alpha)
+                    },
+                ]
+            }
+            _ => unreachable!(),
+        })
+        .collect();
+
+    Ok(Texture {
+        width,
+        height,
+        pixels,
+    })
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Extracts the bits selected by `mask` and scales them to 8 bits
+fn channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+
+    let bits = (value & mask) >> mask.trailing_zeros();
+
+    let max = mask >> mask.trailing_zeros();
+
+    (bits as u64 * 255 / max as u64) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// File and info headers of a `width` x `height` image, without pixels
+    fn header(width: i32, height: i32, depth: u16, compression: u32, info_size: u32) -> Vec<u8> {
+        let mut data = b"BM".to_vec();
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&(FILE_HEADER_SIZE as u32 + info_size).to_le_bytes());
+        data.extend_from_slice(&info_size.to_le_bytes());
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&depth.to_le_bytes());
+        data.extend_from_slice(&compression.to_le_bytes());
+        data.resize(FILE_HEADER_SIZE + INFO_HEADER_SIZE, 0);
+        data
+    }
+
+    #[test]
+    fn rows_are_padded_and_stored_bottom_to_top() {
+        let mut data = header(1, 2, 24, BI_RGB, 40);
+        // BGR plus one byte of padding per row, the bottom row comes first
+        data.extend_from_slice(&[255, 0, 0, 0, 0, 0, 255, 0]);
+
+        let texture = decode(&data).expect("valid bmp");
+
+        assert_eq!((texture.width, texture.height), (1, 2));
+        assert_eq!(texture.pixels, [255, 0, 0, 255, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn bitfields_with_alpha_top_to_bottom() {
+        let mut data = header(2, -1, 32, BI_BITFIELDS, 56);
+        // RGBA masks, highest byte is red
+        for mask in [0xff00_0000u32, 0x00ff_0000, 0x0000_ff00, 0x0000_00ff] {
+            data.extend_from_slice(&mask.to_le_bytes());
+        }
+        data.resize(FILE_HEADER_SIZE + 56, 0);
+        let pixel_offset = data.len() as u32;
+        data[10..14].copy_from_slice(&pixel_offset.to_le_bytes());
+        data.extend_from_slice(&0x1122_3344u32.to_le_bytes());
+        data.extend_from_slice(&0xff00_0080u32.to_le_bytes());
+
+        let texture = decode(&data).expect("valid bmp");
+
+        assert_eq!(texture.pixels, [0x11, 0x22, 0x33, 0x44, 0xff, 0, 0, 0x80]);
+    }
+
+    #[test]
+    fn invalid_files() {
+        let error = |data: &[u8]| decode(data).expect_err("invalid bmp").to_string();
+
+        assert_eq!(error(b"BM"), "not a bmp file");
+        assert_eq!(
+            error(&header(1, 1, 24, BI_RGB, 12)),
+            "OS/2 bitmap headers are not supported"
+        );
+        assert_eq!(error(&header(0, 1, 24, BI_RGB, 40)), "invalid size 0x1");
+        assert_eq!(
+            error(&header(1, 1, 16, BI_RGB, 40)),
+            "unsupported bit depth 16, expected 24 or 32"
+        );
+        assert_eq!(
+            error(&header(1, 1, 8, 1, 40)),
+            "unsupported compression method 1"
+        );
+        assert_eq!(error(&header(1, 1, 24, BI_RGB, 40)), "truncated pixel data");
+    }
+
+    #[test]
+    fn truncated_alpha_mask_is_an_error() {
+        // a version 3 header announces an alpha mask the file does not contain
+        let mut data = header(1, 1, 32, BI_BITFIELDS, 56);
+        data.resize(FILE_HEADER_SIZE + INFO_HEADER_SIZE + 12, 0);
+
+        assert!(decode(&data).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use crate::scop::texture::Texture;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Largest payload of a stored deflate block
+const STORED_BLOCK: usize = u16::MAX as usize;
+
+/// 8 bit RGBA PNG, the pixels are stored in uncompressed deflate blocks
+pub fn encode(texture: &Texture) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&texture.width.to_be_bytes());
+    header.extend_from_slice(&texture.height.to_be_bytes());
+    // bit depth 8, truecolor with alpha, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    // every row starts with its filter type, 0 leaves it as is
+    let row = texture.width as usize * 4;
+    let mut scanlines = Vec::with_capacity((row + 1) * texture.height as usize);
+    for pixels in texture.pixels.chunks_exact(row) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(pixels);
+    }
+
+    let mut png = SIGNATURE.to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+
+    // the checksum covers the type and the data, not the length
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // deflate with a 32K window, no preset dictionary, header check bits for 0x7801
+    let mut stream = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(STORED_BLOCK).peekable();
+
+    // an empty input still needs one final block
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0x00, 0x00, 0xff, 0xff]);
+    }
+
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let length = block.len() as u16;
+
+        stream.push(last as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn stored_blocks_are_split_and_terminated() {
+        assert_eq!(
+            zlib_stored(&[]),
+            [0x78, 0x01, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]
+        );
+
+        let data = vec![7; STORED_BLOCK + 1];
+        let stream = zlib_stored(&data);
+
+        // header, then a full block that is not the last one
+        assert_eq!(stream[2..7], [0, 0xff, 0xff, 0x00, 0x00]);
+
+        let second = 2 + 5 + STORED_BLOCK;
+        assert_eq!(stream[second..second + 5], [1, 1, 0, 0xfe, 0xff]);
+        assert_eq!(stream.len(), second + 5 + 1 + 4);
+        assert_eq!(stream[stream.len() - 4..], adler32(&data).to_be_bytes());
+    }
+
+    #[test]
+    fn encoded_chunks() {
+        let texture = Texture {
+            width: 1,
+            height: 2,
+            pixels: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        };
+
+        let png = encode(&texture);
+
+        assert_eq!(png[..8], SIGNATURE);
+
+        // length, type, width, height, depth, color type, methods, then the crc
+        assert_eq!(png[8..16], [0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(png[16..29], [0, 0, 0, 1, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+        assert_eq!(png[29..33], crc32(&png[12..29]).to_be_bytes());
+
+        // every row is prefixed with filter type 0
+        let stream = zlib_stored(&[0, 1, 2, 3, 4, 0, 5, 6, 7, 8]);
+        let idat = &png[33..];
+        assert_eq!(idat[..4], (stream.len() as u32).to_be_bytes());
+        assert_eq!(idat[4..8], *b"IDAT");
+        assert_eq!(idat[8..8 + stream.len()], stream);
+
+        let crc = &idat[8 + stream.len()..12 + stream.len()];
+        assert_eq!(crc, crc32(&idat[4..8 + stream.len()]).to_be_bytes());
+
+        assert_eq!(
+            png[png.len() - 12..],
+            [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::error::Error;
+
+use crate::scop::texture::Texture;
+
+/// Binary portable graymap (P5) or pixmap (P6), 16 bit samples are reduced to 8 bits
+pub fn decode(data: &[u8]) -> Result<Texture, Box<dyn Error>> {
+    let mut header = Header { data, position: 0 };
+
+    let channels = match header.token()? {
+        "P5" => 1,
+        "P6" => 3,
+        "P2" | "P3" => return Err("ascii pnm files are not supported, expected P5 or P6".into()),
+        magic => return Err(format!("unsupported pnm variant '{magic}'").into()),
+    };
+
+    let width = header.number()?;
+    let height = header.number()?;
+    let max_value = header.number()?;
+
+    if width == 0 || height == 0 {
+        return Err(format!("invalid size {width}x{height}").into());
+    }
+
+    if max_value == 0 || max_value > u16::MAX as u32 {
+        return Err(format!("invalid max value {max_value}").into());
+    }
+
+    let sample_size = if max_value > 255 { 2 } else { 1 };
+
+    // a single whitespace byte separates the header from the samples
+    let samples = &data[header.position + 1..];
+
+    let expected = width as usize * height as usize * channels * sample_size;
+
+    if samples.len() < expected {
+        return Err(format!("truncated pixel data, expected {expected} bytes").into());
+    }
+
+    let samples = samples[..expected].chunks_exact(sample_size).map(|sample| {
+        let value = match sample {
+            [high, low] => u16::from_be_bytes([*high, *low]) as u32,
+            _ => sample[0] as u32,
+        };
+        (value.min(max_value) * 255 / max_value) as u8
+    });
+
+    let samples: Vec<u8> = samples.collect();
+
+    let pixels = match channels {
+        1 => samples
+            .iter()
+            .flat_map(|&grey| [grey, grey, grey, 0xff])
+            .collect(),
+        _ => samples
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff])
+            .collect(),
+    };
+
+    Ok(Texture {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Binary pixmap (P6), the alpha channel is dropped
+pub fn encode(texture: &Texture) -> Vec<u8> {
+    let mut data = format!("P6\n{} {}\n255\n", texture.width, texture.height).into_bytes();
+
+    for pixel in texture.pixels.chunks_exact(4) {
+        data.extend_from_slice(&pixel[..3]);
+    }
+    data
+}
+
+struct Header<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Header<'_> {
+    /// Next whitespace separated token, skipping `#` comments
+    fn token(&mut self) -> Result<&str, Box<dyn Error>> {
+        loop {
+            match self.data.get(self.position) {
+                Some(b'#') => {
+                    while self
+                        .data
+                        .get(self.position)
+                        .is_some_and(|&byte| byte != b'\n')
+                    {
+                        self.position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(_) => break,
+                None => return Err("truncated header".into()),
+            }
+        }
+
+        let start = self.position;
+
+        while self
+            .data
+            .get(self.position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+
+        if self.position >= self.data.len() {
+            return Err("truncated header".into());
+        }
+
+        Ok(std::str::from_utf8(&self.data[start..self.position])?)
+    }
+
+    fn number(&mut self) -> Result<u32, Box<dyn Error>> {
+        let token = self.token()?;
+
+        token
+            .parse()
+            .map_err(|_| format!("invalid header value '{token}'").into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixmap_with_comments() {
+        let mut data = b"P6\n# made by hand\n2 1 # size\n255\n".to_vec();
+        data.extend_from_slice(&[255, 0, 0, 0, 128, 255]);
+
+        let texture = decode(&data).expect("valid ppm");
+
+        assert_eq!((texture.width, texture.height), (2, 1));
+        assert_eq!(texture.pixels, [255, 0, 0, 255, 0, 128, 255, 255]);
+    }
+
+    #[test]
+    fn sixteen_bit_graymap_is_scaled() {
+        let mut data = b"P5 2 1 1000\n".to_vec();
+        data.extend_from_slice(&1000u16.to_be_bytes());
+        data.extend_from_slice(&500u16.to_be_bytes());
+
+        let texture = decode(&data).expect("valid pgm");
+
+        assert_eq!(texture.pixels, [255, 255, 255, 255, 127, 127, 127, 255]);
+    }
+
+    #[test]
+    fn encode_then_decode() {
+        let texture = Texture {
+            width: 2,
+            height: 2,
+            pixels: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16],
+        };
+
+        let data = encode(&texture);
+
+        assert!(data.starts_with(b"P6\n2 2\n255\n"));
+
+        // alpha is not stored
+        let decoded = decode(&data).expect("valid ppm");
+        assert_eq!(
+            decoded.pixels,
+            [1, 2, 3, 255, 5, 6, 7, 255, 9, 10, 11, 255, 13, 14, 15, 255]
+        );
+    }
+
+    #[test]
+    fn invalid_files() {
+        let error = |data: &[u8]| decode(data).expect_err("invalid pnm").to_string();
+
+        assert_eq!(
+            error(b"P3 1 1 255\n1 2 3"),
+            "ascii pnm files are not supported, expected P5 or P6"
+        );
+        assert_eq!(error(b"P6 1"), "truncated header");
+        assert_eq!(error(b"P6 1 x 255\n"), "invalid header value 'x'");
+        assert_eq!(error(b"P6 0 1 255\n"), "invalid size 0x1");
+        assert_eq!(error(b"P6 1 1 70000\n"), "invalid max value 70000");
+        assert_eq!(
+            error(b"P6 1 1 255\n\x01\x02"),
+            "truncated pixel data, expected 3 bytes"
+        );
+    }
+}
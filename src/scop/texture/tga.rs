@@ -0,0 +1,220 @@
+use std::error::Error;
+
+use crate::scop::texture::Texture;
+
+const HEADER_SIZE: usize = 18;
+
+/// Truevision TGA, true color (24/32 bit) or grayscale (8 bit), raw or run-length encoded
+pub fn decode(data: &[u8]) -> Result<Texture, Box<dyn Error>> {
+    if data.len() < HEADER_SIZE {
+        return Err("truncated header".into());
+    }
+
+    let id_length = data[0] as usize;
+    let color_map_type = data[1];
+    let image_type = data[2];
+    let color_map_length = u16::from_le_bytes([data[5], data[6]]) as usize;
+    let color_map_depth = data[7] as usize;
+    let width = u16::from_le_bytes([data[12], data[13]]) as u32;
+    let height = u16::from_le_bytes([data[14], data[15]]) as u32;
+    let depth = data[16];
+    let descriptor = data[17];
+
+    let (grayscale, compressed) = match image_type {
+        2 => (false, false),
+        3 => (true, false),
+        10 => (false, true),
+        11 => (true, true),
+        0 => return Err("file contains no image data".into()),
+        1 | 9 => return Err("color mapped images are not supported".into()),
+        other => return Err(format!("unsupported image type {other}").into()),
+    };
+
+    let bytes_per_pixel = match (grayscale, depth) {
+        (true, 8) => 1,
+        (false, 24) => 3,
+        (false, 32) => 4,
+        _ => return Err(format!("unsupported pixel depth {depth}").into()),
+    };
+
+    if width == 0 || height == 0 {
+        return Err(format!("invalid size {width}x{height}").into());
+    }
+
+    // true color images may still carry a palette, it is skipped
+    let color_map_size = if color_map_type == 1 {
+        color_map_length * color_map_depth.div_ceil(8)
+    } else {
+        0
+    };
+
+    let start = HEADER_SIZE + id_length + color_map_size;
+
+    let body = data.get(start..).ok_or("truncated header")?;
+
+    let amount_pixels = width as usize * height as usize;
+
+    let raw = if compressed {
+        decompress(body, amount_pixels, bytes_per_pixel)?
+    } else {
+        body.get(..amount_pixels * bytes_per_pixel)
+            .ok_or("truncated pixel data")?
+            .to_vec()
+    };
+
+    let has_alpha = bytes_per_pixel == 4 && descriptor & 0x0f != 0;
+
+    let pixels: Vec<[u8; 4]> = raw
+        .chunks_exact(bytes_per_pixel)
+        .map(|pixel| match pixel {
+            [grey] => [*grey, *grey, *grey, 0xff],
+            [b, g, r] => [*r, *g, *b, 0xff],
+            [b, g, r, a] => [*r, *g, *b, if has_alpha { *a } else { 0xff }],
+            _ => unreachable!(),
+        })
+        .collect();
+
+    // rows are stored bottom to top and left to right unless the descriptor says otherwise
+    let right_to_left = descriptor & 0x10 != 0;
+    let top_to_bottom = descriptor & 0x20 != 0;
+
+    let (columns, rows) = (width as usize, height as usize);
+
+    let pixels = (0..amount_pixels)
+        .flat_map(|i| {
+            let (x, y) = (i % columns, i / columns);
+            let x = if right_to_left { columns - 1 - x } else { x };
+            let y = if top_to_bottom { y } else { rows - 1 - y };
+            pixels[y * columns + x]
+        })
+        .collect();
+
+    Ok(Texture {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Expands run-length packets, each is either one pixel repeated or a run of literal pixels
+fn decompress(
+    mut data: &[u8],
+    amount_pixels: usize,
+    bytes_per_pixel: usize,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    // the size in the header is not trusted, every byte left expands to at most 128 pixels
+    let mut pixels =
+        Vec::with_capacity(amount_pixels.min(data.len().saturating_mul(128)) * bytes_per_pixel);
+
+    while pixels.len() < amount_pixels * bytes_per_pixel {
+        let (&packet, rest) = data.split_first().ok_or("truncated run-length data")?;
+
+        let count = (packet & 0x7f) as usize + 1;
+
+        if packet & 0x80 != 0 {
+            let pixel = rest
+                .get(..bytes_per_pixel)
+                .ok_or("truncated run-length data")?;
+
+            for _ in 0..count {
+                pixels.extend_from_slice(pixel);
+            }
+            data = &rest[bytes_per_pixel..];
+        } else {
+            let literal = rest
+                .get(..count * bytes_per_pixel)
+                .ok_or("truncated run-length data")?;
+
+            pixels.extend_from_slice(literal);
+            data = &rest[count * bytes_per_pixel..];
+        }
+    }
+
+    // packets may cross scanlines, but not the end of the image
+    pixels.truncate(amount_pixels * bytes_per_pixel);
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(image_type: u8, width: u16, height: u16, depth: u8, descriptor: u8) -> Vec<u8> {
+        let mut data = vec![0; HEADER_SIZE];
+        data[2] = image_type;
+        data[12..14].copy_from_slice(&width.to_le_bytes());
+        data[14..16].copy_from_slice(&height.to_le_bytes());
+        data[16] = depth;
+        data[17] = descriptor;
+        data
+    }
+
+    #[test]
+    fn raw_true_color_bottom_to_top() {
+        let mut data = header(2, 2, 2, 24, 0);
+        // BGR, the bottom row comes first
+        data.extend_from_slice(&[0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255]);
+
+        let texture = decode(&data).expect("valid tga");
+
+        assert_eq!((texture.width, texture.height), (2, 2));
+        assert_eq!(
+            texture.pixels,
+            [
+                0, 0, 255, 255, 255, 255, 255, 255, // top row
+                255, 0, 0, 255, 0, 255, 0, 255, // bottom row
+            ]
+        );
+    }
+
+    #[test]
+    fn run_length_with_alpha_top_to_bottom() {
+        // 8 bits of alpha, rows stored from the top
+        let mut data = header(10, 3, 1, 32, 0x28);
+        // a run of two pixels, then one literal pixel
+        data.extend_from_slice(&[0x81, 1, 2, 3, 4, 0x00, 5, 6, 7, 8]);
+
+        let texture = decode(&data).expect("valid tga");
+
+        assert_eq!(texture.pixels, [3, 2, 1, 4, 3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+
+    #[test]
+    fn grayscale_right_to_left() {
+        let mut data = header(3, 2, 1, 8, 0x10);
+        data.extend_from_slice(&[10, 20]);
+
+        let texture = decode(&data).expect("valid tga");
+
+        assert_eq!(texture.pixels, [20, 20, 20, 255, 10, 10, 10, 255]);
+    }
+
+    #[test]
+    fn invalid_files() {
+        let error = |data: &[u8]| decode(data).expect_err("invalid tga").to_string();
+
+        assert_eq!(error(&[0; 4]), "truncated header");
+        assert_eq!(
+            error(&header(1, 1, 1, 8, 0)),
+            "color mapped images are not supported"
+        );
+        assert_eq!(error(&header(2, 1, 1, 16, 0)), "unsupported pixel depth 16");
+        assert_eq!(error(&header(2, 0, 1, 24, 0)), "invalid size 0x1");
+        assert_eq!(error(&header(2, 2, 2, 24, 0)), "truncated pixel data");
+        assert_eq!(
+            error(&[header(10, 2, 1, 24, 0), vec![0x81, 1, 2]].concat()),
+            "truncated run-length data"
+        );
+    }
+
+    #[test]
+    fn huge_run_length_header_without_data() {
+        // 65535x65535 at 32 bit would reserve about 17 GB before reading a packet
+        let data = header(10, u16::MAX, u16::MAX, 32, 0);
+
+        assert_eq!(
+            decode(&data).expect_err("invalid tga").to_string(),
+            "truncated run-length data"
+        );
+    }
+}
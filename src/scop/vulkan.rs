@@ -4,6 +4,16 @@ use ash::vk;
 
 use std::error::Error;
 
+use crate::scop::loader::Mesh;
+
+use crate::scop::texture::{Texture, Textures};
+
+use crate::scop::config::Config;
+
+use crate::scop::scene::Scene;
+
+use crate::scop::log::{self, Subsystem};
+
 mod device;
 use crate::scop::vulkan::device::Device;
 
@@ -17,116 +27,262 @@ mod swapchain;
 use crate::scop::vulkan::swapchain::Swapchain;
 
 mod renderpass;
-use crate::scop::vulkan::renderpass::RenderPass;
 
 mod pipeline;
-use crate::scop::vulkan::pipeline::Pipeline;
 
 mod pools;
-use crate::scop::vulkan::pools::Pools;
 
 mod command_buffer;
-use crate::scop::vulkan::command_buffer::CommandBuffer;
 
+mod descriptor;
+
+mod pass;
+
+mod frame;
+
+mod buffer;
+
+mod image;
+
+mod memory;
+use crate::scop::vulkan::memory::Allocator;
+
+mod upload;
+
+mod mesh;
+
+mod texture;
+
+mod target;
+
+mod renderer;
+use crate::scop::vulkan::renderer::Renderer;
+
+mod offscreen;
+
+mod capture;
+
+mod headless;
+pub use crate::scop::vulkan::headless::Headless;
+
+/// Copy of a presented frame, or why it could not be made
+pub type Capture = Result<Texture, Box<dyn Error>>;
+
+/// Renders into the window, through its swapchain
 pub struct Vulkan {
     pub instance: Instance,
     pub surface: Surface,
     pub device: Device,
+    pub allocator: Allocator,
     pub swapchain: Swapchain,
-    pub renderpass: RenderPass,
-    pub pipeline: Pipeline,
-    pub pools: Pools,
-    pub command_buffers: CommandBuffer,
+    pub renderer: Renderer,
+    pub resized: bool,
 }
 
 impl Vulkan {
-    pub fn new(window: &Window) -> Result<Self, Box<dyn Error>> {
+    pub fn new(
+        window: &Window,
+        config: &Config,
+        mesh: &Mesh,
+        textures: &Textures,
+    ) -> Result<Self, Box<dyn Error>> {
         let entry = unsafe { ash::Entry::load()? };
-        let instance = Instance::new(window, &entry)?;
-        let surface = Surface::new(window, &entry, &instance.raw)?;
-        let device = Device::new(&instance.raw, &surface)?;
-        let mut swapchain = Swapchain::new(window, &instance.raw, &surface, &device)?;
-        let renderpass = RenderPass::new(&device, &swapchain)?;
-        let pipeline = Pipeline::new(&device, &swapchain, &renderpass)?;
-        let pools = Pools::new(&device)?;
-        swapchain.create_framebuffers(&device, &renderpass)?;
-        let command_buffers = CommandBuffer::new(
-            &pools,
+        let instance = Instance::new(Some(window), &entry, &config.validation)?;
+
+        // nothing is dropped on its own, every error cleans what exists in reverse order
+        let surface = match Surface::new(window, &entry, &instance.raw) {
+            Ok(surface) => surface,
+            Err(err) => {
+                instance.clean();
+                return Err(err);
+            }
+        };
+
+        let device = match Device::new(&instance.raw, Some(&surface), config.gpu) {
+            Ok(device) => device,
+            Err(err) => {
+                surface.clean();
+                instance.clean();
+                return Err(err);
+            }
+        };
+
+        let mut allocator = Allocator::new(&device);
+
+        let mut swapchain = match Swapchain::new(
+            window,
+            &instance.raw,
+            &surface,
+            &device,
+            &mut allocator,
+            config.vsync,
+        ) {
+            Ok(swapchain) => swapchain,
+            Err(err) => {
+                allocator.clean(&device);
+                device.clean();
+                surface.clean();
+                instance.clean();
+                return Err(err);
+            }
+        };
+
+        let renderer = match Renderer::new(
+            &instance.raw,
             &device,
-            &renderpass,
+            &mut allocator,
             &swapchain,
-            &pipeline,
-            swapchain.framebuffers.len(),
-        )?;
+            swapchain.amount_images,
+            mesh,
+            textures,
+        ) {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                swapchain.clean(&device, &mut allocator);
+                allocator.clean(&device);
+                device.clean();
+                surface.clean();
+                instance.clean();
+                return Err(err);
+            }
+        };
+
+        if let Err(err) = swapchain.create_framebuffers(&device, &renderer.renderpass) {
+            renderer.clean(&device, &mut allocator);
+            swapchain.clean(&device, &mut allocator);
+            allocator.clean(&device);
+            device.clean();
+            surface.clean();
+            instance.clean();
+            return Err(err);
+        }
 
         Ok(Self {
             instance,
             surface,
             device,
+            allocator,
             swapchain,
-            renderpass,
-            pipeline,
-            pools,
-            command_buffers,
+            renderer,
+            resized: false,
         })
     }
 
-    pub fn draw(&mut self) {
-        self.swapchain.current_image =
-            (self.swapchain.current_image + 1) % self.swapchain.amount_images as usize;
+    fn recreate_swapchain(&mut self, window: &Window) -> Result<(), Box<dyn Error>> {
+        let recreated = self.swapchain.recreate(
+            window,
+            &self.surface,
+            &self.device,
+            &mut self.allocator,
+            &self.renderer.renderpass,
+        )?;
 
-        let current_image = self.swapchain.current_image;
+        // the surface is still minimized, frames are skipped until it has an area again
+        if !recreated {
+            self.resized = true;
+            return Ok(());
+        }
 
-        let (image_index, _) = unsafe {
-            self.swapchain
-                .loader
-                .acquire_next_image(
-                    self.swapchain.raw,
-                    u64::MAX,
-                    self.swapchain.images_available[current_image],
-                    vk::Fence::null(),
-                )
-                .expect("failed to get image")
-        };
+        self.renderer
+            .frames
+            .reset_images(self.swapchain.amount_images);
+        Ok(())
+    }
+
+    /// Whether `draw` can capture the frames it presents
+    pub fn can_capture(&self) -> bool {
+        self.swapchain.capturable
+    }
+
+    /// Presents a frame of `scene`. With `capture`, the copy of the presented image is also
+    /// returned, unless the frame was skipped. A failed copy still presents the frame
+    pub fn draw(
+        &mut self,
+        window: &Window,
+        scene: &Scene,
+        capture: bool,
+    ) -> Result<Option<Capture>, Box<dyn Error>> {
+        // reports what the layer found since the previous frame, with --validation-panic
+        self.instance.check_validation();
+
+        let size = window.inner_size();
+
+        // a minimized window has no surface to present to, skip frames until it comes back
+        if size.width == 0 || size.height == 0 {
+            return Ok(None);
+        }
+
+        if self.resized {
+            self.resized = false;
+            return self.recreate_swapchain(window).map(|_| None);
+        }
+
+        let frame = self.renderer.current_frame();
 
         unsafe {
             self.device
                 .logical
-                .wait_for_fences(&[self.swapchain.fences[current_image]], true, u64::MAX)
-                .expect("fence waiting")
+                .wait_for_fences(&[frame.in_flight], true, u64::MAX)?
         };
 
-        unsafe {
-            self.device
-                .logical
-                .reset_fences(&[self.swapchain.fences[current_image]])
-                .expect("reset fences")
+        let acquired = unsafe {
+            self.swapchain.loader.acquire_next_image(
+                self.swapchain.raw,
+                u64::MAX,
+                frame.image_available,
+                vk::Fence::null(),
+            )
+        };
+
+        // a suboptimal image is still presentable, the swapchain is rebuilt after presenting it
+        let image_index = match acquired {
+            Ok((image_index, _)) => image_index,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                return self.recreate_swapchain(window).map(|_| None);
+            }
+            Err(err) => return Err(err.into()),
         };
 
-        let semaphores_available = [self.swapchain.images_available[current_image]];
+        let frames = &mut self.renderer.frames;
 
-        let waiting_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        let frame = &frames.contexts[frames.current];
 
-        let semaphores_finished = [self.swapchain.rendering_finished[current_image]];
+        // with more images than frames in flight an image can be acquired again while the
+        // frame slot that last drew into it is still running
+        let image_in_flight = frames.images_in_flight[image_index as usize];
 
-        let commandbuffers = [self.command_buffers.raw[image_index as usize]];
+        if image_in_flight != vk::Fence::null() && image_in_flight != frame.in_flight {
+            unsafe {
+                self.device
+                    .logical
+                    .wait_for_fences(&[image_in_flight], true, u64::MAX)?
+            };
+        }
 
-        let submit_info = [vk::SubmitInfo::default()
-            .wait_semaphores(&semaphores_available)
-            .wait_dst_stage_mask(&waiting_stages)
-            .command_buffers(&commandbuffers)
-            .signal_semaphores(&semaphores_finished)];
+        frames.images_in_flight[image_index as usize] = frame.in_flight;
 
-        unsafe {
-            self.device
-                .logical
-                .queue_submit(
-                    self.device.graphic_queue,
-                    &submit_info,
-                    self.swapchain.fences[current_image],
-                )
-                .expect("queue submission")
-        };
+        let image_available = frame.image_available;
+
+        self.renderer
+            .record(&self.device, &self.swapchain, image_index as usize, scene)?;
+
+        let semaphores_finished = [self.swapchain.rendering_finished[image_index as usize]];
+
+        self.renderer
+            .submit(&self.device, &[image_available], &semaphores_finished)?;
+
+        self.renderer.frames.advance();
+
+        let captured = capture.then(|| {
+            capture::capture(
+                &self.device,
+                &mut self.allocator,
+                &self.renderer.pools,
+                &self.swapchain,
+                image_index as usize,
+                semaphores_finished[0],
+            )
+        });
 
         let swapchains = [self.swapchain.raw];
 
@@ -137,27 +293,34 @@ impl Vulkan {
             .swapchains(&swapchains)
             .image_indices(&indices);
 
-        unsafe {
+        let presented = unsafe {
             self.swapchain
                 .loader
                 .queue_present(self.device.graphic_queue, &present_info)
-                .expect("queue representation")
         };
+
+        match presented {
+            Ok(false) => Ok(captured),
+            Ok(true) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                self.recreate_swapchain(window).map(|_| captured)
+            }
+            Err(err) => Err(err.into()),
+        }
     }
 }
 
 impl Drop for Vulkan {
     fn drop(&mut self) {
-        unsafe {
-            self.device
-                .logical
-                .device_wait_idle()
-                .expect("failed to wait device idle")
-        };
-        self.pools.clean(&self.device);
-        self.pipeline.clean(&self.device);
-        self.renderpass.clean(&self.device);
-        self.swapchain.clean(&self.device);
+        // cleaning up still beats leaking everything if the device is lost
+        if let Err(err) = unsafe { self.device.logical.device_wait_idle() } {
+            log::error(
+                Subsystem::Device,
+                format_args!("failed to wait for the device before cleaning up: {err}"),
+            );
+        }
+        self.renderer.clean(&self.device, &mut self.allocator);
+        self.swapchain.clean(&self.device, &mut self.allocator);
+        self.allocator.clean(&self.device);
         self.device.clean();
         self.surface.clean();
         self.instance.clean();
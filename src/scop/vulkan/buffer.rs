@@ -0,0 +1,97 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::memory::{Allocation, Allocator, MemoryUsage};
+
+pub struct Buffer {
+    pub raw: vk::Buffer,
+    pub allocation: Allocation,
+    pub size: vk::DeviceSize,
+}
+
+impl Buffer {
+    pub fn new(
+        device: &Device,
+        allocator: &mut Allocator,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        memory: MemoryUsage,
+    ) -> Result<Self, Box<dyn Error>> {
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(size)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+        let buffer = unsafe { device.logical.create_buffer(&buffer_info, None)? };
+
+        let requirements = unsafe { device.logical.get_buffer_memory_requirements(buffer) };
+
+        let allocation = match allocator.allocate(device, requirements, memory) {
+            Ok(allocation) => allocation,
+            Err(err) => {
+                unsafe { device.logical.destroy_buffer(buffer, None) };
+                return Err(err);
+            }
+        };
+
+        let bound = unsafe {
+            device
+                .logical
+                .bind_buffer_memory(buffer, allocation.memory, allocation.offset)
+        };
+
+        let buffer = Self {
+            raw: buffer,
+            allocation,
+            size,
+        };
+
+        if let Err(err) = bound {
+            buffer.clean(device, allocator);
+            return Err(err.into());
+        }
+        Ok(buffer)
+    }
+
+    /// Copies `data` at the start of a host visible buffer
+    pub fn write<T: Copy>(&self, data: &[T]) -> Result<(), Box<dyn Error>> {
+        if self.allocation.mapped.is_null() {
+            return Err("buffer memory is not host visible".into());
+        }
+
+        if size_of_val(data) as vk::DeviceSize > self.size {
+            return Err("data does not fit in the buffer".into());
+        }
+
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr() as *const u8,
+                self.allocation.mapped,
+                size_of_val(data),
+            )
+        };
+        Ok(())
+    }
+
+    /// Copies the whole content of a host visible buffer out
+    pub fn read(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        if self.allocation.mapped.is_null() {
+            return Err("buffer memory is not host visible".into());
+        }
+
+        let mut data = vec![0u8; self.size as usize];
+
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.allocation.mapped, data.as_mut_ptr(), data.len())
+        };
+        Ok(data)
+    }
+
+    pub fn clean(&self, device: &Device, allocator: &mut Allocator) {
+        unsafe { device.logical.destroy_buffer(self.raw, None) };
+        allocator.free(device, &self.allocation);
+    }
+}
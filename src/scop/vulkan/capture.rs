@@ -0,0 +1,211 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::texture::Texture;
+
+use crate::scop::vulkan::buffer::Buffer;
+
+use crate::scop::vulkan::command_buffer::CommandBuffer;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::memory::{Allocator, MemoryUsage};
+
+use crate::scop::vulkan::pools::Pools;
+
+use crate::scop::vulkan::swapchain::Swapchain;
+
+/// Copies the swapchain image `image_index` to host memory once it is rendered, before it is
+/// presented. `rendered` is waited on and signaled again, so presenting also waits for the copy
+pub fn capture(
+    device: &Device,
+    allocator: &mut Allocator,
+    pools: &Pools,
+    swapchain: &Swapchain,
+    image_index: usize,
+    rendered: vk::Semaphore,
+) -> Result<Texture, Box<dyn Error>> {
+    if !swapchain.capturable {
+        return Err("swapchain images cannot be copied on this surface".into());
+    }
+
+    // swapchains pick either of the 8 bit BGRA or RGBA formats, both are easy to swizzle
+    let swap_red_blue = match swapchain.format {
+        vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM => true,
+        vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => false,
+        format => return Err(format!("cannot capture swapchain format {format:?}").into()),
+    };
+
+    let extent = swapchain.extent;
+
+    let readback = Buffer::new(
+        device,
+        allocator,
+        extent.width as vk::DeviceSize * extent.height as vk::DeviceSize * 4,
+        vk::BufferUsageFlags::TRANSFER_DST,
+        MemoryUsage::GpuToCpu,
+    )?;
+
+    let command_buffer = match CommandBuffer::new(pools, device) {
+        Ok(command_buffer) => command_buffer,
+        Err(err) => {
+            readback.clean(device, allocator);
+            return Err(err);
+        }
+    };
+
+    let copied = record_and_submit(
+        device,
+        command_buffer.raw,
+        swapchain.images[image_index],
+        extent,
+        &readback,
+        rendered,
+    );
+
+    command_buffer.clean(device, pools);
+
+    let pixels = copied.and_then(|_| readback.read());
+
+    readback.clean(device, allocator);
+
+    let mut pixels = pixels?;
+
+    for pixel in pixels.chunks_exact_mut(4) {
+        if swap_red_blue {
+            pixel.swap(0, 2);
+        }
+        // the window is composited opaque whatever the alpha the frame ended with
+        pixel[3] = 0xff;
+    }
+
+    Ok(Texture {
+        width: extent.width,
+        height: extent.height,
+        pixels,
+    })
+}
+
+fn record_and_submit(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    readback: &Buffer,
+    rendered: vk::Semaphore,
+) -> Result<(), Box<dyn Error>> {
+    let begin_info =
+        vk::CommandBufferBeginInfo::default().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+    let subresource_range = vk::ImageSubresourceRange::default()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    // the semaphore wait already orders the copy after the render pass writes
+    let to_transfer = [vk::ImageMemoryBarrier::default()
+        .src_access_mask(vk::AccessFlags::empty())
+        .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+        .old_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+        .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource_range)];
+
+    let region = [vk::BufferImageCopy::default()
+        .buffer_offset(0)
+        .buffer_row_length(0)
+        .buffer_image_height(0)
+        .image_subresource(
+            vk::ImageSubresourceLayers::default()
+                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                .mip_level(0)
+                .base_array_layer(0)
+                .layer_count(1),
+        )
+        .image_extent(vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        })];
+
+    let to_present = [vk::ImageMemoryBarrier::default()
+        .src_access_mask(vk::AccessFlags::empty())
+        .dst_access_mask(vk::AccessFlags::empty())
+        .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+        .new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource_range)];
+
+    let copied = [vk::BufferMemoryBarrier::default()
+        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+        .dst_access_mask(vk::AccessFlags::HOST_READ)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .buffer(readback.raw)
+        .offset(0)
+        .size(vk::WHOLE_SIZE)];
+
+    unsafe {
+        device
+            .logical
+            .begin_command_buffer(command_buffer, &begin_info)?;
+
+        device.logical.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &to_transfer,
+        );
+
+        device.logical.cmd_copy_image_to_buffer(
+            command_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            readback.raw,
+            &region,
+        );
+
+        device.logical.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE | vk::PipelineStageFlags::HOST,
+            vk::DependencyFlags::empty(),
+            &[],
+            &copied,
+            &to_present,
+        );
+
+        device.logical.end_command_buffer(command_buffer)?;
+    }
+
+    let semaphores = [rendered];
+
+    let waiting_stages = [vk::PipelineStageFlags::TRANSFER];
+
+    let command_buffers = [command_buffer];
+
+    let submit_info = [vk::SubmitInfo::default()
+        .wait_semaphores(&semaphores)
+        .wait_dst_stage_mask(&waiting_stages)
+        .command_buffers(&command_buffers)
+        .signal_semaphores(&semaphores)];
+
+    // screenshots are rare, simply waiting for the copy is fine
+    unsafe {
+        device
+            .logical
+            .queue_submit(device.graphic_queue, &submit_info, vk::Fence::null())?;
+        device.logical.queue_wait_idle(device.graphic_queue)?;
+    }
+    Ok(())
+}
@@ -4,30 +4,39 @@ use std::error::Error;
 
 use crate::scop::vulkan::device::Device;
 
-use crate::scop::vulkan::swapchain::Swapchain;
+use crate::scop::vulkan::target::PresentTarget;
 
 use crate::scop::vulkan::renderpass::RenderPass;
 
-use crate::scop::vulkan::pipeline::Pipeline;
+use crate::scop::vulkan::pipeline::Pipelines;
 
 use crate::scop::vulkan::pools::Pools;
 
+use crate::scop::vulkan::mesh::MeshBuffer;
+
+use crate::scop::vulkan::pass::{DrawContext, DrawPass};
+
+use crate::scop::scene::Scene;
+
+/// What a frame is recorded with besides its target, borrowed from the renderer
+pub struct RecordContext<'a> {
+    pub renderpass: &'a RenderPass,
+    pub pipelines: &'a Pipelines,
+    pub mesh: &'a MeshBuffer,
+    /// Indexed by the texture of each group
+    pub descriptor_sets: &'a [vk::DescriptorSet],
+    pub passes: &'a [Box<dyn DrawPass>],
+}
+
 pub struct CommandBuffer {
-    pub raw: Vec<vk::CommandBuffer>,
+    pub raw: vk::CommandBuffer,
 }
 
 impl CommandBuffer {
-    pub fn new(
-        pools: &Pools,
-        device: &Device,
-        renderpass: &RenderPass,
-        swapchain: &Swapchain,
-        pipeline: &Pipeline,
-        amount: usize,
-    ) -> Result<Self, Box<dyn Error>> {
+    pub fn new(pools: &Pools, device: &Device) -> Result<Self, Box<dyn Error>> {
         let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::default()
             .command_pool(pools.graphic)
-            .command_buffer_count(amount as u32);
+            .command_buffer_count(1);
 
         let command_buffers = unsafe {
             device
@@ -35,52 +44,114 @@ impl CommandBuffer {
                 .allocate_command_buffers(&command_buffer_allocate_info)?
         };
 
-        for (i, command_buffer) in command_buffers.iter().enumerate() {
-            let command_buffer_begin_info = vk::CommandBufferBeginInfo::default();
+        Ok(Self {
+            raw: command_buffers[0],
+        })
+    }
 
-            unsafe {
-                device
-                    .logical
-                    .begin_command_buffer(*command_buffer, &command_buffer_begin_info)?
-            };
+    /// Re-records the passes of `context` into the framebuffer of the target image `image_index`
+    pub fn record(
+        &self,
+        device: &Device,
+        context: &RecordContext,
+        target: &dyn PresentTarget,
+        image_index: usize,
+        scene: &Scene,
+    ) -> Result<(), Box<dyn Error>> {
+        let command_buffer = self.raw;
+
+        unsafe {
+            device
+                .logical
+                .reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty())?
+        };
 
-            let clear_values = [vk::ClearValue {
+        let command_buffer_begin_info = vk::CommandBufferBeginInfo::default()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+        unsafe {
+            device
+                .logical
+                .begin_command_buffer(command_buffer, &command_buffer_begin_info)?
+        };
+
+        let clear_values = [
+            vk::ClearValue {
                 color: vk::ClearColorValue {
-                    float32: [0.0, 0.0, 0.8, 1.0],
+                    float32: scene.clear_color,
+                },
+            },
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
                 },
-            }];
-
-            let renderpass_begin_info = vk::RenderPassBeginInfo::default()
-                .render_pass(renderpass.raw)
-                .framebuffer(swapchain.framebuffers[i])
-                .render_area(
-                    vk::Rect2D::default()
-                        .offset(vk::Offset2D::default())
-                        .extent(swapchain.extent),
-                )
-                .clear_values(&clear_values);
-
-            unsafe {
-                device.logical.cmd_begin_render_pass(
-                    *command_buffer,
-                    &renderpass_begin_info,
-                    vk::SubpassContents::INLINE,
-                );
-
-                device.logical.cmd_bind_pipeline(
-                    *command_buffer,
-                    vk::PipelineBindPoint::GRAPHICS,
-                    pipeline.raw,
-                );
-
-                device.logical.cmd_draw(*command_buffer, 1, 1, 0, 0);
-                device.logical.cmd_end_render_pass(*command_buffer);
-                device.logical.end_command_buffer(*command_buffer)?
-            }
+            },
+        ];
+
+        let renderpass_begin_info = vk::RenderPassBeginInfo::default()
+            .render_pass(context.renderpass.raw)
+            .framebuffer(target.framebuffer(image_index))
+            .render_area(
+                vk::Rect2D::default()
+                    .offset(vk::Offset2D::default())
+                    .extent(target.extent()),
+            )
+            .clear_values(&clear_values);
+
+        let viewports = [vk::Viewport::default()
+            .x(0.0)
+            .y(0.0)
+            .width(target.extent().width as f32)
+            .height(target.extent().height as f32)
+            .min_depth(0.0)
+            .max_depth(1.0)];
+
+        let scissors = [vk::Rect2D::default()
+            .offset(vk::Offset2D::default().x(0).y(0))
+            .extent(target.extent())];
+
+        unsafe {
+            device.logical.cmd_begin_render_pass(
+                command_buffer,
+                &renderpass_begin_info,
+                vk::SubpassContents::INLINE,
+            );
+
+            device
+                .logical
+                .cmd_set_viewport(command_buffer, 0, &viewports);
+
+            device.logical.cmd_set_scissor(command_buffer, 0, &scissors);
         }
 
-        Ok(Self {
-            raw: command_buffers,
-        })
+        let draw_context = DrawContext {
+            device,
+            command_buffer,
+            pipelines: context.pipelines,
+            mesh: context.mesh,
+            descriptor_sets: context.descriptor_sets,
+        };
+
+        for pass in context.passes.iter() {
+            pass.record(&draw_context, scene);
+        }
+
+        unsafe {
+            device.logical.cmd_end_render_pass(command_buffer);
+        }
+
+        target.record_after(device, command_buffer, image_index);
+
+        unsafe { device.logical.end_command_buffer(command_buffer)? };
+        Ok(())
+    }
+
+    pub fn clean(&self, device: &Device, pools: &Pools) {
+        unsafe {
+            device
+                .logical
+                .free_command_buffers(pools.graphic, &[self.raw])
+        };
     }
 }
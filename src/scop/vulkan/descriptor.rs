@@ -0,0 +1,99 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::math::{Mat4, Vec4};
+
+use crate::scop::vulkan::device::Device;
+
+/// Per-frame uniform block, must match `Uniforms` in shader.vert and shader.frag
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Uniforms {
+    pub model: Mat4,
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub time: f32,
+    pub texture_blend: f32,
+    pub shading: u32,
+    pub _padding: [f32; 1],
+    pub normal_matrix: [[f32; 4]; 3],
+    /// Direction towards the sun light in view space
+    pub light_direction: Vec4,
+    pub light_color: Vec4,
+    pub ambient_color: Vec4,
+    /// Color of the point light at the camera, w is 1 when it is on
+    pub headlight_color: Vec4,
+}
+
+pub struct Descriptors {
+    pub layout: vk::DescriptorSetLayout,
+    pub pool: vk::DescriptorPool,
+}
+
+impl Descriptors {
+    pub fn new(device: &Device, amount_sets: u32) -> Result<Self, Box<dyn Error>> {
+        let bindings = [
+            vk::DescriptorSetLayoutBinding::default()
+                .binding(0)
+                .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+                .descriptor_count(1)
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT),
+            vk::DescriptorSetLayoutBinding::default()
+                .binding(1)
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .descriptor_count(1)
+                .stage_flags(vk::ShaderStageFlags::FRAGMENT),
+        ];
+
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+
+        let layout = unsafe {
+            device
+                .logical
+                .create_descriptor_set_layout(&layout_info, None)?
+        };
+
+        let pool_sizes = [
+            vk::DescriptorPoolSize::default()
+                .ty(vk::DescriptorType::UNIFORM_BUFFER)
+                .descriptor_count(amount_sets),
+            vk::DescriptorPoolSize::default()
+                .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .descriptor_count(amount_sets),
+        ];
+
+        let pool_info = vk::DescriptorPoolCreateInfo::default()
+            .max_sets(amount_sets)
+            .pool_sizes(&pool_sizes);
+
+        let pool = match unsafe { device.logical.create_descriptor_pool(&pool_info, None) } {
+            Ok(pool) => pool,
+            Err(err) => {
+                unsafe { device.logical.destroy_descriptor_set_layout(layout, None) };
+                return Err(err.into());
+            }
+        };
+
+        Ok(Self { layout, pool })
+    }
+
+    pub fn allocate(&self, device: &Device) -> Result<vk::DescriptorSet, Box<dyn Error>> {
+        let layouts = [self.layout];
+
+        let allocate_info = vk::DescriptorSetAllocateInfo::default()
+            .descriptor_pool(self.pool)
+            .set_layouts(&layouts);
+
+        Ok(unsafe { device.logical.allocate_descriptor_sets(&allocate_info)? }[0])
+    }
+
+    pub fn clean(&self, device: &Device) {
+        unsafe {
+            device.logical.destroy_descriptor_pool(self.pool, None);
+            device
+                .logical
+                .destroy_descriptor_set_layout(self.layout, None);
+        }
+    }
+}
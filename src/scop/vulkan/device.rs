@@ -4,6 +4,8 @@ use std::error::Error;
 
 use std::ffi::c_char;
 
+use crate::scop::log::{self, Subsystem};
+
 use crate::scop::vulkan::surface::Surface;
 
 pub struct Device {
@@ -13,29 +15,54 @@ pub struct Device {
     pub transfer_index: u32,
     pub logical: ash::Device,
     pub physical: vk::PhysicalDevice,
+    pub properties: vk::PhysicalDeviceProperties,
+    pub memory_properties: vk::PhysicalDeviceMemoryProperties,
+    /// Optional features that were both supported and enabled
+    pub features: vk::PhysicalDeviceFeatures,
+    pub depth_format: vk::Format,
 }
 
 impl Device {
-    pub fn new(instance: &ash::Instance, surface: &Surface) -> Result<Self, Box<dyn Error>> {
-        let (physical_device, _physical_device_properties) = {
+    pub fn new(
+        instance: &ash::Instance,
+        surface: Option<&Surface>,
+        gpu: Option<usize>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (physical_device, physical_device_properties) = {
             let physical_devices = unsafe { instance.enumerate_physical_devices()? };
 
-            physical_devices
-                .into_iter()
-                .map(|device| {
-                    let device_properties =
-                        unsafe { instance.get_physical_device_properties(device) };
-                    (device, device_properties)
-                })
-                .max_by_key(|(_, properties)| match properties.device_type {
-                    vk::PhysicalDeviceType::DISCRETE_GPU => 3,
-                    vk::PhysicalDeviceType::INTEGRATED_GPU => 2,
-                    vk::PhysicalDeviceType::VIRTUAL_GPU => 1,
-                    _ => 0,
-                })
-                .expect("No physical device found")
+            let mut candidates = physical_devices.into_iter().map(|device| {
+                let device_properties = unsafe { instance.get_physical_device_properties(device) };
+                (device, device_properties)
+            });
+
+            match gpu {
+                Some(index) => candidates
+                    .nth(index)
+                    .ok_or_else(|| format!("no physical device at index {index}"))?,
+                None => candidates
+                    .max_by_key(|(_, properties)| match properties.device_type {
+                        vk::PhysicalDeviceType::DISCRETE_GPU => 3,
+                        vk::PhysicalDeviceType::INTEGRATED_GPU => 2,
+                        vk::PhysicalDeviceType::VIRTUAL_GPU => 1,
+                        _ => 0,
+                    })
+                    .ok_or("no physical device found")?,
+            }
         };
 
+        log::info(
+            Subsystem::Device,
+            format_args!(
+                "using {} ({:?})",
+                physical_device_properties
+                    .device_name_as_c_str()
+                    .unwrap_or_default()
+                    .to_string_lossy(),
+                physical_device_properties.device_type
+            ),
+        );
+
         let queue_family_properties =
             unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
 
@@ -45,15 +72,21 @@ impl Device {
             let mut found_transfer = None;
 
             for (i, queue_family) in queue_family_properties.iter().enumerate() {
-                if queue_family.queue_count > 0
-                    && queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
-                    && unsafe {
+                // without a surface, as when rendering offscreen, any graphic queue will do
+                let presents = match surface {
+                    Some(surface) => unsafe {
                         surface.loader.get_physical_device_surface_support(
                             physical_device,
                             i as u32,
                             surface.raw,
                         )?
-                    }
+                    },
+                    None => true,
+                };
+
+                if queue_family.queue_count > 0
+                    && queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+                    && presents
                 {
                     found_graphic = Some(i as u32);
                 }
@@ -66,25 +99,61 @@ impl Device {
                     found_transfer = Some(i as u32);
                 }
             }
-            (found_graphic.unwrap(), found_transfer.unwrap())
+            (
+                found_graphic.ok_or("no queue family can draw to the target")?,
+                found_transfer.ok_or("no queue family supports transfers")?,
+            )
         };
 
+        let depth_format = [
+            vk::Format::D32_SFLOAT,
+            vk::Format::D24_UNORM_S8_UINT,
+            vk::Format::D32_SFLOAT_S8_UINT,
+        ]
+        .into_iter()
+        .find(|format| {
+            let properties =
+                unsafe { instance.get_physical_device_format_properties(physical_device, *format) };
+            properties
+                .optimal_tiling_features
+                .contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
+        })
+        .ok_or("no supported depth format")?;
+
         let priorities: [f32; 1] = [1.0];
 
-        let queue_infos = [
-            vk::DeviceQueueCreateInfo::default()
-                .queue_family_index(graphic_family_index)
-                .queue_priorities(&priorities),
-            vk::DeviceQueueCreateInfo::default()
-                .queue_family_index(transfer_family_index)
-                .queue_priorities(&priorities),
-        ];
+        let mut queue_infos = vec![vk::DeviceQueueCreateInfo::default()
+            .queue_family_index(graphic_family_index)
+            .queue_priorities(&priorities)];
+
+        // a family may only be listed once, both queues are then the same one
+        if transfer_family_index != graphic_family_index {
+            queue_infos.push(
+                vk::DeviceQueueCreateInfo::default()
+                    .queue_family_index(transfer_family_index)
+                    .queue_priorities(&priorities),
+            );
+        }
+
+        let device_extensions: Vec<*const c_char> = match surface {
+            Some(_) => vec![vk::KHR_SWAPCHAIN_NAME.as_ptr()],
+            None => vec![],
+        };
+
+        let supported_features = unsafe { instance.get_physical_device_features(physical_device) };
 
-        let device_extensions: Vec<*const c_char> = vec![vk::KHR_SWAPCHAIN_NAME.as_ptr()];
+        let features = vk::PhysicalDeviceFeatures::default()
+            .sampler_anisotropy(supported_features.sampler_anisotropy == vk::TRUE)
+            // only needed for gl_PrimitiveID in the fragment shader
+            .geometry_shader(supported_features.geometry_shader == vk::TRUE)
+            // line and point polygon modes, and lines thicker than a pixel
+            .fill_mode_non_solid(supported_features.fill_mode_non_solid == vk::TRUE)
+            .wide_lines(supported_features.wide_lines == vk::TRUE);
 
         let device_create_info = vk::DeviceCreateInfo::default()
             .queue_create_infos(&queue_infos)
-            .enabled_extension_names(&device_extensions);
+            .enabled_extension_names(&device_extensions)
+            .enabled_features(&features);
 
         let logical_device =
             unsafe { instance.create_device(physical_device, &device_create_info, None)? };
@@ -93,6 +162,9 @@ impl Device {
 
         let transfer_queue = unsafe { logical_device.get_device_queue(transfer_family_index, 0) };
 
+        let memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(physical_device) };
+
         Ok(Self {
             graphic_queue,
             transfer_queue,
@@ -100,8 +172,28 @@ impl Device {
             transfer_index: transfer_family_index,
             logical: logical_device,
             physical: physical_device,
+            properties: physical_device_properties,
+            memory_properties,
+            features,
+            depth_format,
         })
     }
+
+    pub fn find_memory_type(
+        &self,
+        type_bits: u32,
+        flags: vk::MemoryPropertyFlags,
+    ) -> Result<u32, Box<dyn Error>> {
+        self.memory_properties.memory_types[..self.memory_properties.memory_type_count as usize]
+            .iter()
+            .enumerate()
+            .position(|(i, memory_type)| {
+                type_bits & (1 << i) != 0 && memory_type.property_flags.contains(flags)
+            })
+            .map(|i| i as u32)
+            .ok_or_else(|| format!("no memory type with {flags:?}").into())
+    }
+
     pub fn clean(&self) {
         unsafe { self.logical.destroy_device(None) };
     }
@@ -0,0 +1,188 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::vulkan::command_buffer::CommandBuffer;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::pools::Pools;
+
+use crate::scop::vulkan::buffer::Buffer;
+
+use crate::scop::vulkan::descriptor::{Descriptors, Uniforms};
+
+use crate::scop::vulkan::memory::{Allocator, MemoryUsage};
+
+use crate::scop::vulkan::texture::TextureBuffer;
+
+pub struct FrameContext {
+    pub command_buffer: CommandBuffer,
+    pub image_available: vk::Semaphore,
+    pub in_flight: vk::Fence,
+    /// Host visible so it can be rewritten once the frame fence is signaled
+    pub uniforms: Buffer,
+    /// One per texture, all pointing at the same uniforms
+    pub descriptor_sets: Vec<vk::DescriptorSet>,
+}
+
+pub struct Frames {
+    pub contexts: Vec<FrameContext>,
+    /// Fence of the last frame that rendered into each swapchain image, null if none did
+    pub images_in_flight: Vec<vk::Fence>,
+    pub current: usize,
+}
+
+impl FrameContext {
+    fn new(
+        device: &Device,
+        allocator: &mut Allocator,
+        pools: &Pools,
+        descriptors: &Descriptors,
+        textures: &[TextureBuffer],
+    ) -> Result<Self, Box<dyn Error>> {
+        let semaphore_info = vk::SemaphoreCreateInfo::default();
+
+        let fence_info = vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED);
+
+        let command_buffer = CommandBuffer::new(pools, device)?;
+
+        let image_available =
+            match unsafe { device.logical.create_semaphore(&semaphore_info, None) } {
+                Ok(semaphore) => semaphore,
+                Err(err) => {
+                    command_buffer.clean(device, pools);
+                    return Err(err.into());
+                }
+            };
+
+        let in_flight = match unsafe { device.logical.create_fence(&fence_info, None) } {
+            Ok(fence) => fence,
+            Err(err) => {
+                unsafe { device.logical.destroy_semaphore(image_available, None) };
+                command_buffer.clean(device, pools);
+                return Err(err.into());
+            }
+        };
+
+        let uniforms = match Buffer::new(
+            device,
+            allocator,
+            size_of::<Uniforms>() as vk::DeviceSize,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            MemoryUsage::CpuToGpu,
+        ) {
+            Ok(uniforms) => uniforms,
+            Err(err) => {
+                unsafe {
+                    device.logical.destroy_fence(in_flight, None);
+                    device.logical.destroy_semaphore(image_available, None);
+                }
+                command_buffer.clean(device, pools);
+                return Err(err);
+            }
+        };
+
+        let buffer_infos = [vk::DescriptorBufferInfo::default()
+            .buffer(uniforms.raw)
+            .offset(0)
+            .range(uniforms.size)];
+
+        // the sets go back with the descriptor pool, they are never freed one by one
+        let mut context = Self {
+            command_buffer,
+            image_available,
+            in_flight,
+            uniforms,
+            descriptor_sets: Vec::with_capacity(textures.len()),
+        };
+
+        for texture in textures.iter() {
+            let descriptor_set = match descriptors.allocate(device) {
+                Ok(descriptor_set) => descriptor_set,
+                Err(err) => {
+                    context.clean(device, allocator, pools);
+                    return Err(err);
+                }
+            };
+
+            let image_infos = [texture.descriptor_info()];
+
+            let writes = [
+                vk::WriteDescriptorSet::default()
+                    .dst_set(descriptor_set)
+                    .dst_binding(0)
+                    .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+                    .buffer_info(&buffer_infos),
+                vk::WriteDescriptorSet::default()
+                    .dst_set(descriptor_set)
+                    .dst_binding(1)
+                    .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .image_info(&image_infos),
+            ];
+
+            unsafe { device.logical.update_descriptor_sets(&writes, &[]) };
+
+            context.descriptor_sets.push(descriptor_set);
+        }
+
+        Ok(context)
+    }
+
+    fn clean(&self, device: &Device, allocator: &mut Allocator, pools: &Pools) {
+        self.command_buffer.clean(device, pools);
+        self.uniforms.clean(device, allocator);
+
+        unsafe {
+            device.logical.destroy_semaphore(self.image_available, None);
+            device.logical.destroy_fence(self.in_flight, None);
+        }
+    }
+}
+
+impl Frames {
+    pub fn new(
+        device: &Device,
+        allocator: &mut Allocator,
+        pools: &Pools,
+        descriptors: &Descriptors,
+        textures: &[TextureBuffer],
+        amount_frames: usize,
+        amount_images: u32,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut contexts = Vec::with_capacity(amount_frames);
+
+        for _ in 0..amount_frames {
+            match FrameContext::new(device, allocator, pools, descriptors, textures) {
+                Ok(context) => contexts.push(context),
+                Err(err) => {
+                    for context in contexts.iter() {
+                        context.clean(device, allocator, pools);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(Self {
+            contexts,
+            images_in_flight: vec![vk::Fence::null(); amount_images as usize],
+            current: 0,
+        })
+    }
+
+    pub fn advance(&mut self) {
+        self.current = (self.current + 1) % self.contexts.len();
+    }
+
+    /// Forgets which frame used which image, the swapchain images were replaced
+    pub fn reset_images(&mut self, amount_images: u32) {
+        self.images_in_flight = vec![vk::Fence::null(); amount_images as usize];
+    }
+
+    pub fn clean(&self, device: &Device, allocator: &mut Allocator, pools: &Pools) {
+        for context in self.contexts.iter() {
+            context.clean(device, allocator, pools);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::config::Config;
+
+use crate::scop::loader::Mesh;
+
+use crate::scop::scene::Scene;
+
+use crate::scop::log::{self, Subsystem};
+
+use crate::scop::texture::Textures;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::instance::Instance;
+
+use crate::scop::vulkan::memory::Allocator;
+
+use crate::scop::vulkan::offscreen::Offscreen;
+
+use crate::scop::vulkan::renderer::Renderer;
+
+/// Renders without a window into an offscreen image, works with software drivers such as
+/// lavapipe. The window size of the config is the size of the rendered images
+pub struct Headless {
+    pub instance: Instance,
+    pub device: Device,
+    pub allocator: Allocator,
+    pub target: Offscreen,
+    pub renderer: Renderer,
+}
+
+impl Headless {
+    pub fn new(config: &Config, mesh: &Mesh, textures: &Textures) -> Result<Self, Box<dyn Error>> {
+        let entry = unsafe { ash::Entry::load()? };
+        let instance = Instance::new(None, &entry, &config.validation)?;
+
+        // nothing is dropped on its own, every error cleans what exists in reverse order
+        let device = match Device::new(&instance.raw, None, config.gpu) {
+            Ok(device) => device,
+            Err(err) => {
+                instance.clean();
+                return Err(err);
+            }
+        };
+
+        let mut allocator = Allocator::new(&device);
+
+        let extent = vk::Extent2D {
+            width: config.width,
+            height: config.height,
+        };
+
+        let mut target = match Offscreen::new(&device, &mut allocator, extent) {
+            Ok(target) => target,
+            Err(err) => {
+                allocator.clean(&device);
+                device.clean();
+                instance.clean();
+                return Err(err);
+            }
+        };
+
+        let renderer = match Renderer::new(
+            &instance.raw,
+            &device,
+            &mut allocator,
+            &target,
+            // the offscreen target has a single image, unlike a swapchain
+            1,
+            mesh,
+            textures,
+        ) {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                target.clean(&device, &mut allocator);
+                allocator.clean(&device);
+                device.clean();
+                instance.clean();
+                return Err(err);
+            }
+        };
+
+        if let Err(err) = target.create_framebuffer(&device, &renderer.renderpass) {
+            renderer.clean(&device, &mut allocator);
+            target.clean(&device, &mut allocator);
+            allocator.clean(&device);
+            device.clean();
+            instance.clean();
+            return Err(err);
+        }
+
+        Ok(Self {
+            instance,
+            device,
+            allocator,
+            target,
+            renderer,
+        })
+    }
+
+    /// Width and height of the rendered images
+    pub fn size(&self) -> (u32, u32) {
+        (self.target.extent.width, self.target.extent.height)
+    }
+
+    /// Draws `scene` and waits for it, returns its RGBA pixels row by row from the top
+    pub fn render(&mut self, scene: &Scene) -> Result<Vec<u8>, Box<dyn Error>> {
+        let in_flight = self.renderer.current_frame().in_flight;
+
+        unsafe {
+            self.device
+                .logical
+                .wait_for_fences(&[in_flight], true, u64::MAX)?
+        };
+
+        self.renderer.record(&self.device, &self.target, 0, scene)?;
+
+        self.renderer.submit(&self.device, &[], &[])?;
+
+        self.renderer.frames.advance();
+
+        // the readback buffer is shared by every frame, nothing can be kept in flight
+        unsafe {
+            self.device
+                .logical
+                .wait_for_fences(&[in_flight], true, u64::MAX)?
+        };
+
+        self.instance.check_validation();
+
+        self.target.pixels()
+    }
+}
+
+impl Drop for Headless {
+    fn drop(&mut self) {
+        // cleaning up still beats leaking everything if the device is lost
+        if let Err(err) = unsafe { self.device.logical.device_wait_idle() } {
+            log::error(
+                Subsystem::Device,
+                format_args!("failed to wait for the device before cleaning up: {err}"),
+            );
+        }
+        self.renderer.clean(&self.device, &mut self.allocator);
+        self.target.clean(&self.device, &mut self.allocator);
+        self.allocator.clean(&self.device);
+        self.device.clean();
+        self.instance.clean();
+    }
+}
@@ -0,0 +1,129 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::memory::{Allocation, Allocator, MemoryUsage};
+
+pub struct Image {
+    pub raw: vk::Image,
+    pub allocation: Allocation,
+    pub view: vk::ImageView,
+}
+
+impl Image {
+    /// Amount of levels down to 1x1, each half the size of the previous one
+    pub fn mip_chain_length(extent: vk::Extent2D) -> u32 {
+        u32::BITS - extent.width.max(extent.height).max(1).leading_zeros()
+    }
+
+    pub fn new(
+        device: &Device,
+        allocator: &mut Allocator,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        mip_levels: u32,
+        usage: vk::ImageUsageFlags,
+        aspect: vk::ImageAspectFlags,
+    ) -> Result<Self, Box<dyn Error>> {
+        let image_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .mip_levels(mip_levels)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+
+        let image = unsafe { device.logical.create_image(&image_info, None)? };
+
+        let requirements = unsafe { device.logical.get_image_memory_requirements(image) };
+
+        let allocation = match allocator.allocate(device, requirements, MemoryUsage::GpuOnly) {
+            Ok(allocation) => allocation,
+            Err(err) => {
+                unsafe { device.logical.destroy_image(image, None) };
+                return Err(err);
+            }
+        };
+
+        let bound = unsafe {
+            device
+                .logical
+                .bind_image_memory(image, allocation.memory, allocation.offset)
+        };
+
+        if let Err(err) = bound {
+            unsafe { device.logical.destroy_image(image, None) };
+            allocator.free(device, &allocation);
+            return Err(err.into());
+        }
+
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(aspect)
+            .base_mip_level(0)
+            .level_count(mip_levels)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        let view_info = vk::ImageViewCreateInfo::default()
+            .image(image)
+            .view_type(vk::ImageViewType::TYPE_2D)
+            .format(format)
+            .subresource_range(subresource_range);
+
+        let view = match unsafe { device.logical.create_image_view(&view_info, None) } {
+            Ok(view) => view,
+            Err(err) => {
+                unsafe { device.logical.destroy_image(image, None) };
+                allocator.free(device, &allocation);
+                return Err(err.into());
+            }
+        };
+
+        Ok(Self {
+            raw: image,
+            allocation,
+            view,
+        })
+    }
+
+    pub fn depth(
+        device: &Device,
+        allocator: &mut Allocator,
+        extent: vk::Extent2D,
+    ) -> Result<Self, Box<dyn Error>> {
+        let aspect = match device.depth_format {
+            vk::Format::D24_UNORM_S8_UINT | vk::Format::D32_SFLOAT_S8_UINT => {
+                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+            }
+            _ => vk::ImageAspectFlags::DEPTH,
+        };
+
+        Self::new(
+            device,
+            allocator,
+            extent,
+            device.depth_format,
+            1,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            aspect,
+        )
+    }
+
+    pub fn clean(&self, device: &Device, allocator: &mut Allocator) {
+        unsafe {
+            device.logical.destroy_image_view(self.view, None);
+            device.logical.destroy_image(self.raw, None);
+        }
+        allocator.free(device, &self.allocation);
+    }
+}
@@ -1,5 +1,7 @@
 use ash::{vk, Entry};
 
+use std::borrow::Cow;
+
 use std::ffi::{c_char, c_void, CStr};
 
 use winit::window::Window;
@@ -8,25 +10,205 @@ use winit::raw_window_handle::HasDisplayHandle;
 
 use std::error::Error;
 
+use std::sync::Mutex;
+
+use crate::scop::config::{Severity, Validation};
+
+use crate::scop::log::{self, Level, Subsystem};
+
+const VALIDATION_LAYER: &CStr = c"VK_LAYER_KHRONOS_validation";
+
 pub struct Instance {
     pub raw: ash::Instance,
-    pub debug_utils: ash::ext::debug_utils::Instance,
+    pub debug_utils: Option<ash::ext::debug_utils::Instance>,
     pub debug_messenger: vk::DebugUtilsMessengerEXT,
+    /// Read by the callback through its user data, boxed so its address stays put
+    messenger: Box<Messenger>,
+}
+
+/// What the debug callback needs to filter messages and remember errors
+struct Messenger {
+    ignored: Vec<String>,
+    panic: bool,
+    errors: Mutex<Vec<String>>,
+}
+
+impl Messenger {
+    fn is_ignored(&self, name: Option<&str>, number: i32) -> bool {
+        self.ignored.iter().any(|id| {
+            // numbers may be copied from the hexadecimal ids in the log
+            let parsed = match id.strip_prefix("0x").or_else(|| id.strip_prefix("0X")) {
+                Some(hex) => i64::from_str_radix(hex, 16).ok(),
+                None => id.parse::<i64>().ok(),
+            };
+
+            Some(id.as_str()) == name
+                || parsed.is_some_and(|id| {
+                    // ids are printed either signed or as their unsigned bits
+                    id == number as i64 || id == number as u32 as i64
+                })
+        })
+    }
+}
+
+/// Text of a nul terminated string the layer handed over, `None` for a null pointer
+unsafe fn c_str<'a>(text: *const c_char) -> Option<Cow<'a, str>> {
+    (!text.is_null()).then(|| unsafe { CStr::from_ptr(text) }.to_string_lossy())
+}
+
+/// The `count` items at `items`, which may be null when there are none
+unsafe fn items<'a, T>(items: *const T, count: u32) -> &'a [T] {
+    if items.is_null() || count == 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(items, count as usize) }
+    }
+}
+
+/// The message followed by the objects it is about and the labels open when it was sent
+fn describe(
+    kind: vk::DebugUtilsMessageTypeFlagsEXT,
+    data: &vk::DebugUtilsMessengerCallbackDataEXT,
+) -> String {
+    let kind = if kind.contains(vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION) {
+        "validation"
+    } else if kind.contains(vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE) {
+        "performance"
+    } else {
+        "general"
+    };
+
+    let name = unsafe { c_str(data.p_message_id_name) };
+
+    let mut text = format!(
+        "{kind} {} ({:#x}): {}",
+        name.as_deref().unwrap_or("-"),
+        data.message_id_number as u32,
+        unsafe { c_str(data.p_message) }.unwrap_or_default()
+    );
+
+    for object in unsafe { items(data.p_objects, data.object_count) } {
+        text += &format!(
+            "\n    object {:?} {:#x}",
+            object.object_type, object.object_handle
+        );
+
+        if let Some(name) = unsafe { c_str(object.p_object_name) } {
+            text += &format!(" '{name}'");
+        }
+    }
+
+    let labels = [
+        ("queue", unsafe {
+            items(data.p_queue_labels, data.queue_label_count)
+        }),
+        ("command buffer", unsafe {
+            items(data.p_cmd_buf_labels, data.cmd_buf_label_count)
+        }),
+    ];
+
+    for (owner, labels) in labels {
+        let names: Vec<_> = labels
+            .iter()
+            .filter_map(|label| unsafe { c_str(label.p_label_name) })
+            .collect();
+
+        if !names.is_empty() {
+            text += &format!("\n    {owner} labels: {}", names.join(" > "));
+        }
+    }
+    text
 }
 
 unsafe extern "system" fn vulkan_debug_utils_callback(
     msg_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
     msg_type: vk::DebugUtilsMessageTypeFlagsEXT,
     callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _: *mut c_void,
+    user_data: *mut c_void,
 ) -> vk::Bool32 {
-    let message = unsafe { CStr::from_ptr((*callback_data).p_message).to_string_lossy() };
-    eprintln!("[DEBUG][{:?}][{:?}] {}", msg_severity, msg_type, message);
+    let (messenger, data) = unsafe { (&*(user_data as *const Messenger), &*callback_data) };
+
+    let name = unsafe { c_str(data.p_message_id_name) };
+
+    if messenger.is_ignored(name.as_deref(), data.message_id_number) {
+        return vk::FALSE;
+    }
+
+    let level = if msg_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+        Level::Error
+    } else if msg_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+        Level::Warning
+    } else if msg_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+        Level::Info
+    } else {
+        Level::Debug
+    };
+
+    let text = describe(msg_type, data);
+
+    log::log(level, Subsystem::Validation, &text);
+
+    // unwinding out of the callback would abort, the error is raised by `check_validation`
+    if messenger.panic
+        && level == Level::Error
+        && let Ok(mut errors) = messenger.errors.lock()
+    {
+        errors.push(text);
+    }
     vk::FALSE
 }
 
+/// Every severity at least as important as `severity`
+fn severity_flags(severity: Severity) -> vk::DebugUtilsMessageSeverityFlagsEXT {
+    let all = [
+        (
+            Severity::Verbose,
+            vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+        ),
+        (Severity::Info, vk::DebugUtilsMessageSeverityFlagsEXT::INFO),
+        (
+            Severity::Warning,
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+        ),
+        (
+            Severity::Error,
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+        ),
+    ];
+
+    all.into_iter()
+        .filter(|(level, _)| *level >= severity)
+        .fold(
+            vk::DebugUtilsMessageSeverityFlagsEXT::empty(),
+            |flags, (_, flag)| flags | flag,
+        )
+}
+
+fn has_layer(entry: &Entry, name: &CStr) -> Result<bool, Box<dyn Error>> {
+    let layers = unsafe { entry.enumerate_instance_layer_properties()? };
+
+    Ok(layers
+        .iter()
+        .any(|layer| layer.layer_name_as_c_str() == Ok(name)))
+}
+
+/// Looks in the implementation and, when given, in the extensions a layer brings along
+fn has_extension(entry: &Entry, layer: Option<&CStr>, name: &CStr) -> Result<bool, Box<dyn Error>> {
+    let extensions = unsafe { entry.enumerate_instance_extension_properties(layer)? };
+
+    Ok(extensions
+        .iter()
+        .any(|extension| extension.extension_name_as_c_str() == Ok(name)))
+}
+
 impl Instance {
-    pub fn new(window: &Window, entry: &Entry) -> Result<Self, Box<dyn Error>> {
+    /// Without a window no surface extension is enabled, only offscreen rendering works.
+    /// The validation layer and debug messenger are only enabled when they are installed
+    pub fn new(
+        window: Option<&Window>,
+        entry: &Entry,
+        validation: &Validation,
+    ) -> Result<Self, Box<dyn Error>> {
         let app_info: vk::ApplicationInfo = vk::ApplicationInfo::default()
             .application_name(c"scop")
             .application_version(vk::make_api_version(0, 1, 0, 0))
@@ -34,59 +216,154 @@ impl Instance {
             .engine_version(vk::make_api_version(0, 1, 0, 0))
             .api_version(vk::API_VERSION_1_3);
 
-        let layer_name: Vec<*const c_char> = vec![c"VK_LAYER_KHRONOS_validation".as_ptr()];
+        let layer = validation.enabled && has_layer(entry, VALIDATION_LAYER)?;
+
+        if validation.enabled && !layer {
+            log::warning(
+                Subsystem::Instance,
+                format_args!(
+                    "{} is not installed, running without validation",
+                    VALIDATION_LAYER.to_string_lossy()
+                ),
+            );
+        }
+
+        let debug = layer
+            && (has_extension(entry, None, ash::ext::debug_utils::NAME)?
+                || has_extension(entry, Some(VALIDATION_LAYER), ash::ext::debug_utils::NAME)?);
+
+        if layer && !debug {
+            log::warning(
+                Subsystem::Instance,
+                format_args!(
+                    "{} is not available, validation messages use the default output",
+                    ash::ext::debug_utils::NAME.to_string_lossy()
+                ),
+            );
+        }
+
+        if layer {
+            log::debug(Subsystem::Instance, "validation layer enabled");
+        }
+
+        let layer_name: Vec<*const c_char> = if layer {
+            vec![VALIDATION_LAYER.as_ptr()]
+        } else {
+            vec![]
+        };
 
         let mut instance_extensions: Vec<*const c_char> = vec![];
 
-        let window_extensions =
-            ash_window::enumerate_required_extensions(window.display_handle()?.as_raw())?;
+        if let Some(window) = window {
+            let window_extensions =
+                ash_window::enumerate_required_extensions(window.display_handle()?.as_raw())?;
+
+            instance_extensions.extend_from_slice(window_extensions);
+        }
 
-        for extension in window_extensions.iter() {
-            instance_extensions.push(*extension);
+        if debug {
+            instance_extensions.push(ash::ext::debug_utils::NAME.as_ptr());
         }
 
-        instance_extensions.push(ash::ext::debug_utils::NAME.as_ptr());
+        let messenger = Box::new(Messenger {
+            ignored: validation.ignored.clone(),
+            panic: validation.panic,
+            errors: Mutex::new(Vec::new()),
+        });
 
         let mut debug_create_info = vk::DebugUtilsMessengerCreateInfoEXT::default()
-            .message_severity(
-                vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
-                    | vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE
-                    | vk::DebugUtilsMessageSeverityFlagsEXT::INFO
-                    | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
-            )
+            .message_severity(severity_flags(validation.severity))
             .message_type(
                 vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
                     | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
                     | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
             )
-            .pfn_user_callback(Some(vulkan_debug_utils_callback));
+            .pfn_user_callback(Some(vulkan_debug_utils_callback))
+            .user_data(&*messenger as *const Messenger as *mut c_void);
 
-        let instance_create_info = vk::InstanceCreateInfo::default()
-            .push_next(&mut debug_create_info)
+        let mut instance_create_info = vk::InstanceCreateInfo::default()
             .application_info(&app_info)
             .enabled_layer_names(&layer_name)
             .enabled_extension_names(&instance_extensions);
 
+        if debug {
+            instance_create_info = instance_create_info.push_next(&mut debug_create_info);
+        }
+
         let instance = unsafe { entry.create_instance(&instance_create_info, None)? };
 
-        let debug_utils = ash::ext::debug_utils::Instance::new(entry, &instance);
+        let (debug_utils, debug_messenger) = if debug {
+            let debug_utils = ash::ext::debug_utils::Instance::new(entry, &instance);
+
+            let debug_messenger =
+                match unsafe { debug_utils.create_debug_utils_messenger(&debug_create_info, None) }
+                {
+                    Ok(debug_messenger) => debug_messenger,
+                    Err(err) => {
+                        unsafe { instance.destroy_instance(None) };
+                        return Err(err.into());
+                    }
+                };
 
-        let debug_messenger =
-            unsafe { debug_utils.create_debug_utils_messenger(&debug_create_info, None)? };
+            (Some(debug_utils), debug_messenger)
+        } else {
+            (None, vk::DebugUtilsMessengerEXT::null())
+        };
 
         Ok(Self {
             raw: instance,
             debug_utils,
             debug_messenger,
+            messenger,
         })
     }
 
+    /// Panics with the validation errors reported so far when `--validation-panic` is set
+    pub fn check_validation(&self) {
+        if !self.messenger.panic {
+            return;
+        }
+
+        let errors = match self.messenger.errors.lock() {
+            Ok(mut errors) => std::mem::take(&mut *errors),
+            Err(_) => return,
+        };
+
+        if !errors.is_empty() {
+            panic!("validation errors:\n{}", errors.join("\n"));
+        }
+    }
+
     pub fn clean(&self) {
         unsafe {
-            self.debug_utils
-                .destroy_debug_utils_messenger(self.debug_messenger, None);
+            if let Some(debug_utils) = self.debug_utils.as_ref() {
+                debug_utils.destroy_debug_utils_messenger(self.debug_messenger, None);
+            }
 
             self.raw.destroy_instance(None)
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignored_ids_match_by_name_or_number() {
+        let messenger = Messenger {
+            ignored: ["VUID-a", "42", "0xfffffffe", "-3"]
+                .map(String::from)
+                .to_vec(),
+            panic: false,
+            errors: Mutex::new(Vec::new()),
+        };
+
+        assert!(messenger.is_ignored(Some("VUID-a"), 0));
+        assert!(messenger.is_ignored(None, 42));
+        // the same id printed unsigned in hexadecimal, and signed
+        assert!(messenger.is_ignored(None, -2));
+        assert!(messenger.is_ignored(None, -3));
+        assert!(!messenger.is_ignored(Some("VUID-b"), 7));
+    }
+}
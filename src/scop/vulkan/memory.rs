@@ -0,0 +1,375 @@
+use ash::vk;
+
+use std::error::Error;
+
+use std::ffi::c_void;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::log::{self, Subsystem};
+
+/// Size of the blocks resources are sub-allocated from, bigger resources get their own block
+const BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryUsage {
+    /// Only touched by the gpu, filled through transfers
+    GpuOnly,
+    /// Written by the cpu every frame or once before a transfer
+    CpuToGpu,
+    /// Written by a transfer, read back by the cpu
+    GpuToCpu,
+}
+
+impl MemoryUsage {
+    fn required(self) -> vk::MemoryPropertyFlags {
+        match self {
+            MemoryUsage::GpuOnly => vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            MemoryUsage::CpuToGpu | MemoryUsage::GpuToCpu => {
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
+            }
+        }
+    }
+}
+
+pub struct Allocation {
+    pub memory: vk::DeviceMemory,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+    /// Points at `offset` inside the persistently mapped block, null for gpu only memory
+    pub mapped: *mut u8,
+    block: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryStats {
+    pub blocks: usize,
+    pub allocations: usize,
+    pub reserved: vk::DeviceSize,
+    pub used: vk::DeviceSize,
+}
+
+struct Range {
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+}
+
+struct Block {
+    memory: vk::DeviceMemory,
+    memory_type: u32,
+    size: vk::DeviceSize,
+    mapped: *mut c_void,
+    /// Free ranges sorted by offset, neighbours are merged on free
+    free: Vec<Range>,
+    allocations: usize,
+}
+
+impl Block {
+    fn allocate(
+        &mut self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize> {
+        let (index, offset) = self.free.iter().enumerate().find_map(|(i, range)| {
+            let offset = range.offset.next_multiple_of(alignment);
+            (offset + size <= range.offset + range.size).then_some((i, offset))
+        })?;
+
+        let range = self.free.remove(index);
+
+        let end = offset + size;
+
+        if end < range.offset + range.size {
+            self.free.insert(
+                index,
+                Range {
+                    offset: end,
+                    size: range.offset + range.size - end,
+                },
+            );
+        }
+
+        if offset > range.offset {
+            self.free.insert(
+                index,
+                Range {
+                    offset: range.offset,
+                    size: offset - range.offset,
+                },
+            );
+        }
+
+        self.allocations += 1;
+        Some(offset)
+    }
+
+    fn free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        let index = self.free.partition_point(|range| range.offset < offset);
+
+        self.free.insert(index, Range { offset, size });
+
+        if index + 1 < self.free.len()
+            && self.free[index].offset + self.free[index].size == self.free[index + 1].offset
+        {
+            self.free[index].size += self.free.remove(index + 1).size;
+        }
+
+        if index > 0 && self.free[index - 1].offset + self.free[index - 1].size == offset {
+            self.free[index - 1].size += self.free.remove(index).size;
+        }
+
+        self.allocations -= 1;
+    }
+}
+
+pub struct Allocator {
+    blocks: Vec<Option<Block>>,
+    /// Linear and optimal resources may share a block, keeping every allocation on this
+    /// boundary avoids aliasing them on the same page
+    granularity: vk::DeviceSize,
+    stats: MemoryStats,
+}
+
+impl Allocator {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            blocks: Vec::new(),
+            granularity: device.properties.limits.buffer_image_granularity,
+            stats: MemoryStats::default(),
+        }
+    }
+
+    pub fn allocate(
+        &mut self,
+        device: &Device,
+        requirements: vk::MemoryRequirements,
+        usage: MemoryUsage,
+    ) -> Result<Allocation, Box<dyn Error>> {
+        let memory_type =
+            device.find_memory_type(requirements.memory_type_bits, usage.required())?;
+
+        let alignment = requirements.alignment.max(self.granularity);
+
+        let size = requirements.size.next_multiple_of(alignment);
+
+        let found = self.blocks.iter_mut().enumerate().find_map(|(i, block)| {
+            let block = block
+                .as_mut()
+                .filter(|block| block.memory_type == memory_type)?;
+            block.allocate(size, alignment).map(|offset| (i, offset))
+        });
+
+        let (block, offset) = match found {
+            Some(found) => found,
+            None => {
+                let index = self.create_block(device, memory_type, size.max(BLOCK_SIZE))?;
+                let block = self.blocks[index].as_mut().ok_or("memory block vanished")?;
+                let offset = block
+                    .allocate(size, alignment)
+                    .ok_or("fresh memory block too small")?;
+                (index, offset)
+            }
+        };
+
+        let Some(block_ref) = self.blocks[block].as_ref() else {
+            return Err("memory block vanished".into());
+        };
+
+        let mapped = if block_ref.mapped.is_null() {
+            std::ptr::null_mut()
+        } else {
+            unsafe { (block_ref.mapped as *mut u8).add(offset as usize) }
+        };
+
+        self.stats.allocations += 1;
+        self.stats.used += size;
+
+        Ok(Allocation {
+            memory: block_ref.memory,
+            offset,
+            size,
+            mapped,
+            block,
+        })
+    }
+
+    fn create_block(
+        &mut self,
+        device: &Device,
+        memory_type: u32,
+        size: vk::DeviceSize,
+    ) -> Result<usize, Box<dyn Error>> {
+        let allocate_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(size)
+            .memory_type_index(memory_type);
+
+        let memory = unsafe { device.logical.allocate_memory(&allocate_info, None)? };
+
+        let host_visible = device.memory_properties.memory_types[memory_type as usize]
+            .property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+
+        // every host visible block stays mapped, whatever the usage that created it
+        let mapped = if host_visible {
+            match unsafe {
+                device
+                    .logical
+                    .map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+            } {
+                Ok(mapped) => mapped,
+                Err(err) => {
+                    unsafe { device.logical.free_memory(memory, None) };
+                    return Err(err.into());
+                }
+            }
+        } else {
+            std::ptr::null_mut()
+        };
+
+        let block = Block {
+            memory,
+            memory_type,
+            size,
+            mapped,
+            free: vec![Range { offset: 0, size }],
+            allocations: 0,
+        };
+
+        self.stats.blocks += 1;
+        self.stats.reserved += size;
+
+        match self.blocks.iter().position(Option::is_none) {
+            Some(index) => {
+                self.blocks[index] = Some(block);
+                Ok(index)
+            }
+            None => {
+                self.blocks.push(Some(block));
+                Ok(self.blocks.len() - 1)
+            }
+        }
+    }
+
+    /// Returns the range to its block, only oversized blocks go back to the driver once empty
+    pub fn free(&mut self, device: &Device, allocation: &Allocation) {
+        let Some(block) = self.blocks[allocation.block].as_mut() else {
+            return;
+        };
+
+        block.free(allocation.offset, allocation.size);
+
+        self.stats.allocations -= 1;
+        self.stats.used -= allocation.size;
+
+        if block.allocations == 0 && block.size > BLOCK_SIZE {
+            self.release_block(device, allocation.block);
+        }
+    }
+
+    fn release_block(&mut self, device: &Device, index: usize) {
+        if let Some(block) = self.blocks[index].take() {
+            unsafe { device.logical.free_memory(block.memory, None) };
+
+            self.stats.blocks -= 1;
+            self.stats.reserved -= block.size;
+        }
+    }
+
+    pub fn stats(&self) -> MemoryStats {
+        self.stats
+    }
+
+    pub fn clean(&mut self, device: &Device) {
+        let stats = self.stats();
+
+        if stats.allocations > 0 {
+            log::warning(
+                Subsystem::Device,
+                format_args!(
+                    "{} gpu allocations ({} bytes) still alive at exit",
+                    stats.allocations, stats.used
+                ),
+            );
+        }
+
+        for index in 0..self.blocks.len() {
+            self.release_block(device, index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Only the free list is exercised, no memory is ever allocated
+    fn block(size: vk::DeviceSize) -> Block {
+        Block {
+            memory: vk::DeviceMemory::null(),
+            memory_type: 0,
+            size,
+            mapped: std::ptr::null_mut(),
+            free: vec![Range { offset: 0, size }],
+            allocations: 0,
+        }
+    }
+
+    fn free_ranges(block: &Block) -> Vec<(vk::DeviceSize, vk::DeviceSize)> {
+        block
+            .free
+            .iter()
+            .map(|range| (range.offset, range.size))
+            .collect()
+    }
+
+    #[test]
+    fn allocations_split_the_free_range() {
+        let mut block = block(1024);
+
+        assert_eq!(block.allocate(100, 4), Some(0));
+        assert_eq!(block.allocate(100, 4), Some(100));
+        assert_eq!(free_ranges(&block), [(200, 824)]);
+        assert_eq!(block.allocations, 2);
+    }
+
+    #[test]
+    fn alignment_leaves_the_gap_free() {
+        let mut block = block(1024);
+
+        assert_eq!(block.allocate(10, 1), Some(0));
+        assert_eq!(block.allocate(100, 256), Some(256));
+        assert_eq!(free_ranges(&block), [(10, 246), (356, 668)]);
+
+        // the gap is used by a later allocation that fits
+        assert_eq!(block.allocate(200, 8), Some(16));
+        assert_eq!(free_ranges(&block), [(10, 6), (216, 40), (356, 668)]);
+    }
+
+    #[test]
+    fn exact_fit_and_exhaustion() {
+        let mut block = block(256);
+
+        assert_eq!(block.allocate(256, 16), Some(0));
+        assert!(free_ranges(&block).is_empty());
+        assert_eq!(block.allocate(1, 1), None);
+    }
+
+    #[test]
+    fn free_merges_with_both_neighbours() {
+        let mut block = block(300);
+
+        let offsets: Vec<_> = (0..3).map(|_| block.allocate(100, 1)).collect();
+        assert_eq!(offsets, [Some(0), Some(100), Some(200)]);
+
+        block.free(0, 100);
+        block.free(200, 100);
+        assert_eq!(free_ranges(&block), [(0, 100), (200, 100)]);
+
+        block.free(100, 100);
+        assert_eq!(free_ranges(&block), [(0, 300)]);
+        assert_eq!(block.allocations, 0);
+
+        // the merged range serves an allocation none of the pieces could
+        assert_eq!(block.allocate(300, 1), Some(0));
+    }
+}
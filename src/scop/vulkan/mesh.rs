@@ -0,0 +1,118 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::loader::{Group, Material, Mesh};
+
+use crate::scop::texture::Textures;
+
+use crate::scop::vulkan::buffer::Buffer;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::memory::Allocator;
+
+use crate::scop::vulkan::upload::Upload;
+
+/// Material of one draw, pushed as constants, must match `Material` in shader.frag
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct MaterialConstants {
+    pub ambient: [f32; 4],
+    /// Alpha holds the dissolve
+    pub diffuse: [f32; 4],
+    /// W holds the shininess
+    pub specular: [f32; 4],
+    pub illumination: u32,
+    pub _padding: [u32; 3],
+}
+
+impl From<&Material> for MaterialConstants {
+    fn from(material: &Material) -> Self {
+        let [ar, ag, ab] = material.ambient;
+        let [dr, dg, db] = material.diffuse;
+        let [sr, sg, sb] = material.specular;
+
+        Self {
+            ambient: [ar, ag, ab, 1.0],
+            diffuse: [dr, dg, db, material.dissolve],
+            specular: [sr, sg, sb, material.shininess],
+            illumination: material.illumination,
+            _padding: [0; 3],
+        }
+    }
+}
+
+pub struct MeshBuffer {
+    pub vertices: Buffer,
+    pub indices: Buffer,
+    pub groups: Vec<Group>,
+    /// One per mesh material, followed by the default one
+    pub materials: Vec<MaterialConstants>,
+    /// Texture sampled by each mesh material, followed by the one of the default material
+    pub textures: Vec<usize>,
+}
+
+impl MeshBuffer {
+    pub fn new(
+        device: &Device,
+        allocator: &mut Allocator,
+        upload: &mut Upload,
+        mesh: &Mesh,
+        textures: &Textures,
+    ) -> Result<Self, Box<dyn Error>> {
+        let vertices = upload.buffer(
+            device,
+            allocator,
+            &mesh.vertices,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+        )?;
+
+        let indices = match upload.buffer(
+            device,
+            allocator,
+            &mesh.indices,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+        ) {
+            Ok(indices) => indices,
+            Err(err) => {
+                vertices.clean(device, allocator);
+                return Err(err);
+            }
+        };
+
+        Ok(Self {
+            vertices,
+            indices,
+            groups: mesh.groups.clone(),
+            materials: mesh
+                .materials
+                .iter()
+                .chain([&Material::default()])
+                .map(MaterialConstants::from)
+                .collect(),
+            textures: (0..mesh.materials.len())
+                .map(Some)
+                .chain([None])
+                .map(|material| textures.index(material))
+                .collect(),
+        })
+    }
+
+    pub fn material(&self, group: &Group) -> &MaterialConstants {
+        let default = self.materials.len() - 1;
+
+        &self.materials[group.material.unwrap_or(default)]
+    }
+
+    pub fn texture(&self, group: &Group) -> usize {
+        let default = self.textures.len() - 1;
+
+        self.textures[group.material.unwrap_or(default)]
+    }
+
+    pub fn clean(&self, device: &Device, allocator: &mut Allocator) {
+        self.vertices.clean(device, allocator);
+        self.indices.clean(device, allocator);
+    }
+}
@@ -0,0 +1,199 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::vulkan::buffer::Buffer;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::image::Image;
+
+use crate::scop::vulkan::memory::{Allocator, MemoryUsage};
+
+use crate::scop::vulkan::renderpass::RenderPass;
+
+use crate::scop::vulkan::target::PresentTarget;
+
+/// Same encoding as the swapchain, the pixels read back are what the window would show
+pub const OFFSCREEN_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+/// A single color image rendered without a window, copied to host memory after every frame
+pub struct Offscreen {
+    pub color: Image,
+    pub depth: Image,
+    pub framebuffer: vk::Framebuffer,
+    /// Tightly packed RGBA rows of the last frame
+    pub readback: Buffer,
+    pub extent: vk::Extent2D,
+}
+
+impl Offscreen {
+    pub fn new(
+        device: &Device,
+        allocator: &mut Allocator,
+        extent: vk::Extent2D,
+    ) -> Result<Self, Box<dyn Error>> {
+        let color = Image::new(
+            device,
+            allocator,
+            extent,
+            OFFSCREEN_FORMAT,
+            1,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::ImageAspectFlags::COLOR,
+        )?;
+
+        let depth = match Image::depth(device, allocator, extent) {
+            Ok(depth) => depth,
+            Err(err) => {
+                color.clean(device, allocator);
+                return Err(err);
+            }
+        };
+
+        let readback = match Buffer::new(
+            device,
+            allocator,
+            extent.width as vk::DeviceSize * extent.height as vk::DeviceSize * 4,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            MemoryUsage::GpuToCpu,
+        ) {
+            Ok(readback) => readback,
+            Err(err) => {
+                depth.clean(device, allocator);
+                color.clean(device, allocator);
+                return Err(err);
+            }
+        };
+
+        Ok(Self {
+            color,
+            depth,
+            framebuffer: vk::Framebuffer::null(),
+            readback,
+            extent,
+        })
+    }
+
+    pub fn create_framebuffer(
+        &mut self,
+        device: &Device,
+        renderpass: &RenderPass,
+    ) -> Result<(), Box<dyn Error>> {
+        let attachments = [self.color.view, self.depth.view];
+
+        let framebuffer_info = vk::FramebufferCreateInfo::default()
+            .render_pass(renderpass.raw)
+            .attachments(&attachments)
+            .width(self.extent.width)
+            .height(self.extent.height)
+            .layers(1);
+
+        self.framebuffer = unsafe { device.logical.create_framebuffer(&framebuffer_info, None)? };
+        Ok(())
+    }
+
+    /// RGBA pixels of the last frame, only valid once its fence is signaled
+    pub fn pixels(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.readback.read()
+    }
+
+    pub fn clean(&self, device: &Device, allocator: &mut Allocator) {
+        unsafe { device.logical.destroy_framebuffer(self.framebuffer, None) };
+        self.readback.clean(device, allocator);
+        self.depth.clean(device, allocator);
+        self.color.clean(device, allocator);
+    }
+}
+
+impl PresentTarget for Offscreen {
+    fn format(&self) -> vk::Format {
+        OFFSCREEN_FORMAT
+    }
+
+    fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    fn final_layout(&self) -> vk::ImageLayout {
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+    }
+
+    fn framebuffer(&self, _image_index: usize) -> vk::Framebuffer {
+        self.framebuffer
+    }
+
+    /// The readback buffer is shared, every frame is waited for before the next one
+    fn frames_in_flight(&self) -> usize {
+        1
+    }
+
+    fn record_after(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        _image_index: usize,
+    ) {
+        // the render pass already moved the image to TRANSFER_SRC, its writes still have to
+        // be made visible to the copy
+        let rendered = [vk::MemoryBarrier::default()
+            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_READ)];
+
+        let region = [vk::BufferImageCopy::default()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(
+                vk::ImageSubresourceLayers::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .mip_level(0)
+                    .base_array_layer(0)
+                    .layer_count(1),
+            )
+            .image_extent(vk::Extent3D {
+                width: self.extent.width,
+                height: self.extent.height,
+                depth: 1,
+            })];
+
+        let copied = [vk::BufferMemoryBarrier::default()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(self.readback.raw)
+            .offset(0)
+            .size(vk::WHOLE_SIZE)];
+
+        unsafe {
+            device.logical.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &rendered,
+                &[],
+                &[],
+            );
+
+            device.logical.cmd_copy_image_to_buffer(
+                command_buffer,
+                self.color.raw,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                self.readback.raw,
+                &region,
+            );
+
+            device.logical.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[],
+                &copied,
+                &[],
+            );
+        }
+    }
+}
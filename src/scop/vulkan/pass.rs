@@ -0,0 +1,113 @@
+use ash::vk;
+
+use crate::scop::scene::{DrawMode, Scene};
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::mesh::{MaterialConstants, MeshBuffer};
+
+use crate::scop::vulkan::pipeline::{Pipeline, PipelineVariant, Pipelines};
+
+/// Resources a pass may record against, borrowed from the renderer for one frame
+pub struct DrawContext<'a> {
+    pub device: &'a Device,
+    pub command_buffer: vk::CommandBuffer,
+    pub pipelines: &'a Pipelines,
+    pub mesh: &'a MeshBuffer,
+    /// Indexed by the texture of each group
+    pub descriptor_sets: &'a [vk::DescriptorSet],
+}
+
+/// A step recorded inside the render pass, in the order the passes are listed
+pub trait DrawPass {
+    fn record(&self, context: &DrawContext, scene: &Scene);
+}
+
+/// Draws every group of the loaded mesh with its material, in the polygon mode of the scene
+pub struct MeshPass;
+
+impl DrawPass for MeshPass {
+    fn record(&self, context: &DrawContext, scene: &Scene) {
+        let variant = match scene.draw_mode {
+            DrawMode::Solid | DrawMode::Overlay => PipelineVariant::Fill,
+            DrawMode::Wireframe => PipelineVariant::Line,
+            DrawMode::Points => PipelineVariant::Point,
+        };
+
+        // devices without fillModeNonSolid only draw solid
+        let pipeline = context
+            .pipelines
+            .get(variant)
+            .unwrap_or_else(|| context.pipelines.fill());
+
+        draw_mesh(context, pipeline);
+    }
+}
+
+/// Draws the edges of the mesh over what `MeshPass` drew, in overlay mode only
+pub struct OverlayPass;
+
+impl DrawPass for OverlayPass {
+    fn record(&self, context: &DrawContext, scene: &Scene) {
+        if scene.draw_mode != DrawMode::Overlay {
+            return;
+        }
+
+        if let Some(pipeline) = context.pipelines.get(PipelineVariant::Overlay) {
+            draw_mesh(context, pipeline);
+        }
+    }
+}
+
+fn draw_mesh(context: &DrawContext, pipeline: &Pipeline) {
+    let device = &context.device.logical;
+
+    let command_buffer = context.command_buffer;
+
+    let mesh = context.mesh;
+
+    unsafe {
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.raw,
+        );
+
+        device.cmd_bind_vertex_buffers(command_buffer, 0, &[mesh.vertices.raw], &[0]);
+
+        device.cmd_bind_index_buffer(command_buffer, mesh.indices.raw, 0, vk::IndexType::UINT32);
+
+        for group in mesh.groups.iter() {
+            let material = mesh.material(group);
+
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.layout,
+                0,
+                &[context.descriptor_sets[mesh.texture(group)]],
+                &[],
+            );
+
+            device.cmd_push_constants(
+                command_buffer,
+                pipeline.layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                std::slice::from_raw_parts(
+                    (material as *const MaterialConstants).cast::<u8>(),
+                    size_of::<MaterialConstants>(),
+                ),
+            );
+
+            device.cmd_draw_indexed(
+                command_buffer,
+                group.index_count,
+                1,
+                group.first_index,
+                0,
+                0,
+            );
+        }
+    }
+}
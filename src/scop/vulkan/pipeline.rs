@@ -4,16 +4,26 @@ use std::error::Error;
 
 use crate::scop::vulkan::device::Device;
 
-use crate::scop::vulkan::swapchain::Swapchain;
-
 use crate::scop::vulkan::renderpass::RenderPass;
 
+use crate::scop::vulkan::mesh::MaterialConstants;
+
+use crate::scop::loader::Vertex;
+
+use crate::scop::log::{self, Subsystem};
+
 const FRAGMENT_SHADER_BYTES: &[u8] = include_bytes!("../../../shaders/shader.frag.spv");
 
+/// Same fragment shader, telling faces apart with `gl_PrimitiveID` which needs `geometryShader`
+const FRAGMENT_PRIMITIVE_SHADER_BYTES: &[u8] =
+    include_bytes!("../../../shaders/shader_primitive.frag.spv");
+
+const WIREFRAME_SHADER_BYTES: &[u8] = include_bytes!("../../../shaders/wireframe.frag.spv");
+
 const VERTEX_SHADER_BYTES: &[u8] = include_bytes!("../../../shaders/shader.vert.spv");
 
 fn u8_to_u32_slice(bytes: &[u8]) -> Vec<u32> {
-    if bytes.len() % 4 != 0 {
+    if !bytes.len().is_multiple_of(4) {
         panic!("spv file must be aligned with 4 bytes")
     }
 
@@ -22,6 +32,28 @@ fn u8_to_u32_slice(bytes: &[u8]) -> Vec<u32> {
         .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
         .collect()
 }
+/// Ways the mesh can be rasterized, each one is its own pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipelineVariant {
+    Fill,
+    Line,
+    Point,
+    /// Edges in a flat color, pulled towards the camera to be drawn over `Fill`
+    Overlay,
+}
+
+impl PipelineVariant {
+    pub const ALL: [Self; 4] = [Self::Fill, Self::Line, Self::Point, Self::Overlay];
+
+    fn polygon_mode(self) -> vk::PolygonMode {
+        match self {
+            Self::Fill => vk::PolygonMode::FILL,
+            Self::Line | Self::Overlay => vk::PolygonMode::LINE,
+            Self::Point => vk::PolygonMode::POINT,
+        }
+    }
+}
+
 pub struct Pipeline {
     pub raw: vk::Pipeline,
     pub layout: vk::PipelineLayout,
@@ -30,8 +62,9 @@ pub struct Pipeline {
 impl Pipeline {
     pub fn new(
         device: &Device,
-        swapchain: &Swapchain,
         renderpass: &RenderPass,
+        set_layout: vk::DescriptorSetLayout,
+        variant: PipelineVariant,
     ) -> Result<Self, Box<dyn Error>> {
         let vertex_shader = u8_to_u32_slice(VERTEX_SHADER_BYTES);
 
@@ -43,7 +76,13 @@ impl Pipeline {
                 .create_shader_module(&vextex_shader_create_info, None)?
         };
 
-        let fragment_shader = u8_to_u32_slice(FRAGMENT_SHADER_BYTES);
+        let fragment_shader = u8_to_u32_slice(if variant == PipelineVariant::Overlay {
+            WIREFRAME_SHADER_BYTES
+        } else if device.features.geometry_shader == vk::TRUE {
+            FRAGMENT_PRIMITIVE_SHADER_BYTES
+        } else {
+            FRAGMENT_SHADER_BYTES
+        });
 
         let fragment_shader_create_info =
             vk::ShaderModuleCreateInfo::default().code(&fragment_shader);
@@ -66,36 +105,75 @@ impl Pipeline {
 
         let shader_stages = vec![vertex_shader_stage, fragment_shader_stage];
 
-        let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::default();
+        let vertex_bindings = [vk::VertexInputBindingDescription::default()
+            .binding(0)
+            .stride(size_of::<Vertex>() as u32)
+            .input_rate(vk::VertexInputRate::VERTEX)];
+
+        let vertex_attributes = [
+            vk::VertexInputAttributeDescription::default()
+                .location(0)
+                .binding(0)
+                .format(vk::Format::R32G32B32_SFLOAT)
+                .offset(std::mem::offset_of!(Vertex, position) as u32),
+            vk::VertexInputAttributeDescription::default()
+                .location(1)
+                .binding(0)
+                .format(vk::Format::R32G32B32_SFLOAT)
+                .offset(std::mem::offset_of!(Vertex, normal) as u32),
+            vk::VertexInputAttributeDescription::default()
+                .location(2)
+                .binding(0)
+                .format(vk::Format::R32G32_SFLOAT)
+                .offset(std::mem::offset_of!(Vertex, texcoord) as u32),
+        ];
+
+        let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&vertex_bindings)
+            .vertex_attribute_descriptions(&vertex_attributes);
 
         let input_assembly_info = vk::PipelineInputAssemblyStateCreateInfo::default()
-            .topology(vk::PrimitiveTopology::POINT_LIST);
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
 
-        let viewports = [vk::Viewport::default()
-            .x(0.0)
-            .y(0.0)
-            .width(swapchain.extent.width as f32)
-            .height(swapchain.extent.height as f32)
-            .min_depth(0.0)
-            .max_depth(1.0)];
+        // viewport and scissor are set while recording so resizing does not rebuild the pipeline
+        let viewport_info = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
 
-        let scissors = [vk::Rect2D::default()
-            .offset(vk::Offset2D::default().x(0).y(0))
-            .extent(swapchain.extent)];
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
 
-        let viewport_info = vk::PipelineViewportStateCreateInfo::default()
-            .viewports(&viewports)
-            .scissors(&scissors);
+        let dynamic_state_info =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let line_width = if device.features.wide_lines == vk::TRUE {
+            device.properties.limits.line_width_range[1].min(2.0)
+        } else {
+            1.0
+        };
+
+        let overlay = variant == PipelineVariant::Overlay;
 
         let rasterizer_info = vk::PipelineRasterizationStateCreateInfo::default()
-            .line_width(1.0)
+            .line_width(line_width)
             .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
             .cull_mode(vk::CullModeFlags::NONE)
-            .polygon_mode(vk::PolygonMode::FILL);
+            .polygon_mode(variant.polygon_mode())
+            .depth_bias_enable(overlay)
+            .depth_bias_constant_factor(-1.0)
+            .depth_bias_slope_factor(-1.0);
 
         let multisampler_info = vk::PipelineMultisampleStateCreateInfo::default()
             .rasterization_samples(vk::SampleCountFlags::TYPE_1);
 
+        let depth_stencil_info = vk::PipelineDepthStencilStateCreateInfo::default()
+            .depth_test_enable(true)
+            .depth_write_enable(!overlay)
+            .depth_compare_op(if overlay {
+                vk::CompareOp::LESS_OR_EQUAL
+            } else {
+                vk::CompareOp::LESS
+            });
+
         let colorblend_attachments = [vk::PipelineColorBlendAttachmentState::default()
             .blend_enable(true)
             .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
@@ -114,7 +192,16 @@ impl Pipeline {
         let colorblend_info =
             vk::PipelineColorBlendStateCreateInfo::default().attachments(&colorblend_attachments);
 
-        let pipeline_layout_info = vk::PipelineLayoutCreateInfo::default();
+        let set_layouts = [set_layout];
+
+        let push_constant_ranges = [vk::PushConstantRange::default()
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .offset(0)
+            .size(size_of::<MaterialConstants>() as u32)];
+
+        let pipeline_layout_info = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(&set_layouts)
+            .push_constant_ranges(&push_constant_ranges);
 
         let pipeline_layout = unsafe {
             device
@@ -129,22 +216,37 @@ impl Pipeline {
             .viewport_state(&viewport_info)
             .rasterization_state(&rasterizer_info)
             .multisample_state(&multisampler_info)
+            .depth_stencil_state(&depth_stencil_info)
             .color_blend_state(&colorblend_info)
+            .dynamic_state(&dynamic_state_info)
             .layout(pipeline_layout)
             .render_pass(renderpass.raw)
             .subpass(0)];
 
-        let graphic_pipeline = unsafe {
-            device
-                .logical
-                .create_graphics_pipelines(vk::PipelineCache::null(), &pipeline_info, None)
-                .expect("failed to create graphical pipeline")[0]
+        let created = unsafe {
+            device.logical.create_graphics_pipelines(
+                vk::PipelineCache::null(),
+                &pipeline_info,
+                None,
+            )
         };
         unsafe {
             device.logical.destroy_shader_module(fragment_module, None);
             device.logical.destroy_shader_module(vertex_module, None);
         };
 
+        let graphic_pipeline = match created {
+            Ok(pipelines) => pipelines[0],
+            Err((_, err)) => {
+                unsafe {
+                    device
+                        .logical
+                        .destroy_pipeline_layout(pipeline_layout, None)
+                };
+                return Err(format!("failed to create the {variant:?} pipeline: {err}").into());
+            }
+        };
+
         Ok(Self {
             raw: graphic_pipeline,
             layout: pipeline_layout,
@@ -158,3 +260,62 @@ impl Pipeline {
         }
     }
 }
+
+/// A pipeline for every variant the device supports, `Fill` always is
+pub struct Pipelines {
+    variants: Vec<(PipelineVariant, Pipeline)>,
+}
+
+impl Pipelines {
+    pub fn new(
+        device: &Device,
+        renderpass: &RenderPass,
+        set_layout: vk::DescriptorSetLayout,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut pipelines = Self {
+            variants: Vec::new(),
+        };
+
+        if device.features.fill_mode_non_solid != vk::TRUE {
+            log::warning(
+                Subsystem::Device,
+                "fillModeNonSolid is not supported, every draw mode is solid",
+            );
+        }
+
+        for variant in PipelineVariant::ALL {
+            // line and point polygon modes need fillModeNonSolid
+            if variant != PipelineVariant::Fill && device.features.fill_mode_non_solid != vk::TRUE {
+                continue;
+            }
+
+            match Pipeline::new(device, renderpass, set_layout, variant) {
+                Ok(pipeline) => pipelines.variants.push((variant, pipeline)),
+                Err(err) => {
+                    pipelines.clean(device);
+                    return Err(err);
+                }
+            }
+        }
+        Ok(pipelines)
+    }
+
+    /// `None` when the device cannot draw this variant
+    pub fn get(&self, variant: PipelineVariant) -> Option<&Pipeline> {
+        self.variants
+            .iter()
+            .find(|(other, _)| *other == variant)
+            .map(|(_, pipeline)| pipeline)
+    }
+
+    pub fn fill(&self) -> &Pipeline {
+        self.get(PipelineVariant::Fill)
+            .expect("the fill pipeline is always created")
+    }
+
+    pub fn clean(&self, device: &Device) {
+        for (_, pipeline) in self.variants.iter() {
+            pipeline.clean(device);
+        }
+    }
+}
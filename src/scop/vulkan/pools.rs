@@ -25,10 +25,20 @@ impl Pools {
             .queue_family_index(device.transfer_index)
             .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER);
 
-        let transfer_command_pool = unsafe {
+        let transfer_command_pool = match unsafe {
             device
                 .logical
-                .create_command_pool(&transfer_command_pool_info, None)?
+                .create_command_pool(&transfer_command_pool_info, None)
+        } {
+            Ok(pool) => pool,
+            Err(err) => {
+                unsafe {
+                    device
+                        .logical
+                        .destroy_command_pool(graphics_command_pool, None)
+                };
+                return Err(err.into());
+            }
         };
 
         Ok(Self {
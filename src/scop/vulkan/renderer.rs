@@ -0,0 +1,282 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::loader::Mesh;
+
+use crate::scop::texture::Textures;
+
+use crate::scop::scene::Scene;
+
+use crate::scop::math::{Mat3, Mat4, Vec4};
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::renderpass::RenderPass;
+
+use crate::scop::vulkan::pipeline::Pipelines;
+
+use crate::scop::vulkan::pools::Pools;
+
+use crate::scop::vulkan::descriptor::{Descriptors, Uniforms};
+
+use crate::scop::vulkan::pass::{DrawPass, MeshPass, OverlayPass};
+
+use crate::scop::vulkan::command_buffer::RecordContext;
+
+use crate::scop::vulkan::frame::{FrameContext, Frames};
+
+use crate::scop::vulkan::memory::Allocator;
+
+use crate::scop::vulkan::upload::Upload;
+
+use crate::scop::vulkan::mesh::MeshBuffer;
+
+use crate::scop::vulkan::texture::TextureBuffer;
+
+use crate::scop::vulkan::target::PresentTarget;
+
+/// Everything drawing a frame needs that does not depend on where the frame goes
+pub struct Renderer {
+    pub renderpass: RenderPass,
+    pub descriptors: Descriptors,
+    pub pipelines: Pipelines,
+    pub pools: Pools,
+    pub mesh: MeshBuffer,
+    /// In the order of `Textures::images`
+    pub textures: Vec<TextureBuffer>,
+    pub frames: Frames,
+    pub passes: Vec<Box<dyn DrawPass>>,
+}
+
+impl Renderer {
+    /// `amount_images` is how many images the target cycles through
+    pub fn new(
+        instance: &ash::Instance,
+        device: &Device,
+        allocator: &mut Allocator,
+        target: &dyn PresentTarget,
+        amount_images: u32,
+        mesh: &Mesh,
+        textures: &Textures,
+    ) -> Result<Self, Box<dyn Error>> {
+        let renderpass = RenderPass::new(device, target)?;
+
+        let amount_frames = target.frames_in_flight();
+
+        // every frame has a descriptor set per texture
+        let amount_sets = (amount_frames * textures.images.len()) as u32;
+
+        // nothing is dropped on its own, every error cleans what exists in reverse order
+        let descriptors = match Descriptors::new(device, amount_sets) {
+            Ok(descriptors) => descriptors,
+            Err(err) => {
+                renderpass.clean(device);
+                return Err(err);
+            }
+        };
+
+        let pipelines = match Pipelines::new(device, &renderpass, descriptors.layout) {
+            Ok(pipelines) => pipelines,
+            Err(err) => {
+                descriptors.clean(device);
+                renderpass.clean(device);
+                return Err(err);
+            }
+        };
+
+        let pools = match Pools::new(device) {
+            Ok(pools) => pools,
+            Err(err) => {
+                pipelines.clean(device);
+                descriptors.clean(device);
+                renderpass.clean(device);
+                return Err(err);
+            }
+        };
+
+        let mut upload = match Upload::begin(device, &pools) {
+            Ok(upload) => upload,
+            Err(err) => {
+                pools.clean(device);
+                pipelines.clean(device);
+                descriptors.clean(device);
+                renderpass.clean(device);
+                return Err(err);
+            }
+        };
+
+        let mesh = match MeshBuffer::new(device, allocator, &mut upload, mesh, textures) {
+            Ok(mesh) => mesh,
+            Err(err) => {
+                upload.cancel(device, allocator, &pools);
+                pools.clean(device);
+                pipelines.clean(device);
+                descriptors.clean(device);
+                renderpass.clean(device);
+                return Err(err);
+            }
+        };
+
+        let mut texture_buffers = Vec::with_capacity(textures.images.len());
+
+        for image in textures.images.iter() {
+            match TextureBuffer::new(instance, device, allocator, &mut upload, image) {
+                Ok(texture) => texture_buffers.push(texture),
+                Err(err) => {
+                    for texture in texture_buffers.iter() {
+                        texture.clean(device, allocator);
+                    }
+                    mesh.clean(device, allocator);
+                    upload.cancel(device, allocator, &pools);
+                    pools.clean(device);
+                    pipelines.clean(device);
+                    descriptors.clean(device);
+                    renderpass.clean(device);
+                    return Err(err);
+                }
+            }
+        }
+
+        let textures = texture_buffers;
+
+        let uploaded = upload.submit(device, allocator, &pools);
+
+        let frames = uploaded.and_then(|_| {
+            Frames::new(
+                device,
+                allocator,
+                &pools,
+                &descriptors,
+                &textures,
+                amount_frames,
+                amount_images,
+            )
+        });
+
+        let frames = match frames {
+            Ok(frames) => frames,
+            Err(err) => {
+                for texture in textures.iter() {
+                    texture.clean(device, allocator);
+                }
+                mesh.clean(device, allocator);
+                pools.clean(device);
+                pipelines.clean(device);
+                descriptors.clean(device);
+                renderpass.clean(device);
+                return Err(err);
+            }
+        };
+
+        Ok(Self {
+            renderpass,
+            descriptors,
+            pipelines,
+            pools,
+            mesh,
+            textures,
+            frames,
+            passes: vec![Box::new(MeshPass), Box::new(OverlayPass)],
+        })
+    }
+
+    pub fn current_frame(&self) -> &FrameContext {
+        &self.frames.contexts[self.frames.current]
+    }
+
+    /// Writes the uniforms of the current frame and records its command buffer, drawing
+    /// into the image `image_index` of the target. The frame fence must be signaled
+    pub fn record(
+        &self,
+        device: &Device,
+        target: &dyn PresentTarget,
+        image_index: usize,
+        scene: &Scene,
+    ) -> Result<(), Box<dyn Error>> {
+        let frame = self.current_frame();
+
+        let extent = target.extent();
+
+        let lighting = &scene.lighting;
+
+        frame.uniforms.write(&[Uniforms {
+            model: scene.model,
+            view: scene.view,
+            projection: Mat4::perspective(
+                45f32.to_radians(),
+                extent.width as f32 / extent.height as f32,
+                0.1,
+                100.0,
+            ),
+            time: scene.time,
+            texture_blend: scene.texture_blend,
+            shading: scene.shading as u32,
+            _padding: [0.0; 1],
+            normal_matrix: Mat3::normal_matrix(scene.view * scene.model).to_std140(),
+            light_direction: (-scene.view.transform_vector(lighting.direction))
+                .normalize()
+                .extend(0.0),
+            light_color: lighting.color.extend(1.0),
+            ambient_color: lighting.ambient.extend(1.0),
+            headlight_color: match lighting.headlight {
+                Some(color) => color.extend(1.0),
+                None => Vec4::ZERO,
+            },
+        }])?;
+
+        let context = RecordContext {
+            renderpass: &self.renderpass,
+            pipelines: &self.pipelines,
+            mesh: &self.mesh,
+            descriptor_sets: &frame.descriptor_sets,
+            passes: &self.passes,
+        };
+
+        frame
+            .command_buffer
+            .record(device, &context, target, image_index, scene)
+    }
+
+    /// Submits the command buffer of the current frame, signaling its fence once done
+    pub fn submit(
+        &self,
+        device: &Device,
+        wait: &[vk::Semaphore],
+        signal: &[vk::Semaphore],
+    ) -> Result<(), Box<dyn Error>> {
+        let frame = self.current_frame();
+
+        let waiting_stages = vec![vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT; wait.len()];
+
+        let commandbuffers = [frame.command_buffer.raw];
+
+        let submit_info = [vk::SubmitInfo::default()
+            .wait_semaphores(wait)
+            .wait_dst_stage_mask(&waiting_stages)
+            .command_buffers(&commandbuffers)
+            .signal_semaphores(signal)];
+
+        // reset only once the submit is sure to happen, or the next wait never returns
+        unsafe {
+            device.logical.reset_fences(&[frame.in_flight])?;
+            device
+                .logical
+                .queue_submit(device.graphic_queue, &submit_info, frame.in_flight)?
+        };
+        Ok(())
+    }
+
+    /// The device must be idle
+    pub fn clean(&self, device: &Device, allocator: &mut Allocator) {
+        self.mesh.clean(device, allocator);
+        for texture in self.textures.iter() {
+            texture.clean(device, allocator);
+        }
+        self.frames.clean(device, allocator, &self.pools);
+        self.pools.clean(device);
+        self.pipelines.clean(device);
+        self.descriptors.clean(device);
+        self.renderpass.clean(device);
+    }
+}
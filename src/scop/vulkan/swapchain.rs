@@ -4,26 +4,38 @@ use winit::window::Window;
 
 use std::error::Error;
 
+use crate::scop::log::{self, Subsystem};
+
 use crate::scop::vulkan::device::Device;
 
 use crate::scop::vulkan::renderpass::RenderPass;
 
 use crate::scop::vulkan::surface::Surface;
 
+use crate::scop::vulkan::image::Image;
+
+use crate::scop::vulkan::memory::Allocator;
+
+use crate::scop::vulkan::target::PresentTarget;
+
+/// Recording a frame while the previous one renders keeps both the cpu and the gpu busy
+const FRAMES_IN_FLIGHT: usize = 2;
+
 pub struct Swapchain {
     pub raw: vk::SwapchainKHR,
     pub loader: ash::khr::swapchain::Device,
     pub images: Vec<vk::Image>,
     pub images_view: Vec<vk::ImageView>,
     pub framebuffers: Vec<vk::Framebuffer>,
+    pub depth: Option<Image>,
     pub format: vk::Format,
-    pub color_space: vk::ColorSpaceKHR,
     pub extent: vk::Extent2D,
-    pub images_available: Vec<vk::Semaphore>,
+    /// One per image, the presentation engine may still hold it when a frame slot comes back
     pub rendering_finished: Vec<vk::Semaphore>,
-    pub fences: Vec<vk::Fence>,
     pub amount_images: u32,
-    pub current_image: usize,
+    pub vsync: bool,
+    /// Images can be copied from, for screenshots
+    pub capturable: bool,
 }
 
 impl Swapchain {
@@ -32,7 +44,39 @@ impl Swapchain {
         instance: &ash::Instance,
         surface: &Surface,
         device: &Device,
+        allocator: &mut Allocator,
+        vsync: bool,
     ) -> Result<Self, Box<dyn Error>> {
+        let mut swapchain = Self {
+            raw: vk::SwapchainKHR::null(),
+            loader: ash::khr::swapchain::Device::new(instance, &device.logical),
+            images: Vec::new(),
+            images_view: Vec::new(),
+            framebuffers: Vec::new(),
+            depth: None,
+            format: vk::Format::UNDEFINED,
+            extent: vk::Extent2D::default(),
+            rendering_finished: Vec::new(),
+            amount_images: 0,
+            vsync,
+            capturable: false,
+        };
+
+        if let Err(err) = swapchain.create(window, surface, device, allocator) {
+            swapchain.clean(device, allocator);
+            return Err(err);
+        }
+        Ok(swapchain)
+    }
+
+    /// Builds the swapchain and its images view, retiring the previous swapchain if any
+    fn create(
+        &mut self,
+        window: &Window,
+        surface: &Surface,
+        device: &Device,
+        allocator: &mut Allocator,
+    ) -> Result<(), Box<dyn Error>> {
         let queue_family = [device.graphic_index];
 
         let capabilities = unsafe {
@@ -47,16 +91,21 @@ impl Swapchain {
                 .get_physical_device_surface_present_modes(device.physical, surface.raw)?
         };
 
-        let present_mode = present_modes
-            .into_iter()
-            .max_by_key(|mode| match *mode {
-                vk::PresentModeKHR::MAILBOX => 3,
-                vk::PresentModeKHR::IMMEDIATE => 2,
-                vk::PresentModeKHR::FIFO => 1,
-                vk::PresentModeKHR::FIFO_RELAXED => 0,
-                _ => 0,
-            })
-            .expect("no valid vulkan present mode ");
+        // FIFO is the only mode the spec guarantees, and the only one that waits for vblank
+        let present_mode = if self.vsync {
+            vk::PresentModeKHR::FIFO
+        } else {
+            present_modes
+                .into_iter()
+                .max_by_key(|mode| match *mode {
+                    vk::PresentModeKHR::MAILBOX => 3,
+                    vk::PresentModeKHR::IMMEDIATE => 2,
+                    vk::PresentModeKHR::FIFO => 1,
+                    vk::PresentModeKHR::FIFO_RELAXED => 0,
+                    _ => 0,
+                })
+                .ok_or("no valid vulkan present mode")?
+        };
 
         let formats = unsafe {
             surface
@@ -78,7 +127,7 @@ impl Swapchain {
                     vk::Format::R8G8B8A8_UNORM => 1,
                     _ => 0,
                 })
-                .expect("no supported surface format found on this device")
+                .ok_or("no supported surface format found on this device")?
         };
 
         let image_count = if capabilities.max_image_count == 0 {
@@ -88,20 +137,21 @@ impl Swapchain {
                 .min(capabilities.max_image_count)
         };
 
-        let swapchain_extent = if capabilities.current_extent.width != u32::MAX {
-            capabilities.current_extent
+        let swapchain_extent = surface_extent(window, &capabilities);
+
+        if swapchain_extent.width == 0 || swapchain_extent.height == 0 {
+            return Err("cannot create a swapchain for a minimized window".into());
+        }
+
+        // screenshots copy the presented image out, not every surface allows it
+        let capturable = capabilities
+            .supported_usage_flags
+            .contains(vk::ImageUsageFlags::TRANSFER_SRC);
+
+        let image_usage = if capturable {
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC
         } else {
-            vk::Extent2D {
-                // clamp 100.clamp(50, 200) clamp check si la value est bien entre les deux value donner
-                width: window.inner_size().width.clamp(
-                    capabilities.min_image_extent.width,
-                    capabilities.max_image_extent.width,
-                ),
-                height: window.inner_size().height.clamp(
-                    capabilities.min_image_extent.height,
-                    capabilities.max_image_extent.height,
-                ),
-            }
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
         };
 
         let swapchain_create_info = vk::SwapchainCreateInfoKHR::default()
@@ -111,21 +161,32 @@ impl Swapchain {
             .image_color_space(color_space)
             .image_extent(swapchain_extent)
             .image_array_layers(1)
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_usage(image_usage)
             .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
             .queue_family_indices(&queue_family)
             .pre_transform(capabilities.current_transform)
             .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
-            .present_mode(present_mode);
+            .present_mode(present_mode)
+            .old_swapchain(self.raw);
 
-        let swapchain_loader = ash::khr::swapchain::Device::new(instance, &device.logical);
+        let swapchain = unsafe { self.loader.create_swapchain(&swapchain_create_info, None)? };
 
-        let swapchain = unsafe { swapchain_loader.create_swapchain(&swapchain_create_info, None)? };
+        // the retired swapchain is still ours to destroy once the new one exists
+        unsafe { self.loader.destroy_swapchain(self.raw, None) };
+        self.raw = swapchain;
 
-        let swapchain_images = unsafe { swapchain_loader.get_swapchain_images(swapchain)? };
+        let swapchain_images = unsafe { self.loader.get_swapchain_images(swapchain)? };
 
         let amount_images = swapchain_images.len() as u32;
 
+        log::debug(
+            Subsystem::Swapchain,
+            format_args!(
+                "{amount_images} {format:?} images of {}x{}, {present_mode:?}",
+                swapchain_extent.width, swapchain_extent.height
+            ),
+        );
+
         let mut swapchain_images_views = Vec::with_capacity(swapchain_images.len());
 
         for image in swapchain_images.iter() {
@@ -149,45 +210,32 @@ impl Swapchain {
             swapchain_images_views.push(images_view);
         }
 
-        let mut images_available = vec![];
-
-        let mut rendering_finished = vec![];
+        self.depth = Some(Image::depth(device, allocator, swapchain_extent)?);
 
-        let mut fences = vec![];
+        self.images = swapchain_images;
+        self.images_view = swapchain_images_views;
+        self.format = format;
+        self.extent = swapchain_extent;
+        self.capturable = capturable;
 
-        let semaphore_info = vk::SemaphoreCreateInfo::default();
+        if self.amount_images != amount_images {
+            self.amount_images = amount_images;
+            self.create_sync_objects(device)?;
+        }
+        Ok(())
+    }
 
-        let fence_info = vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED);
+    fn create_sync_objects(&mut self, device: &Device) -> Result<(), Box<dyn Error>> {
+        self.clean_sync_objects(device);
 
-        for _ in 0..amount_images {
-            let semaphore_available =
-                unsafe { device.logical.create_semaphore(&semaphore_info, None)? };
+        let semaphore_info = vk::SemaphoreCreateInfo::default();
 
+        for _ in 0..self.amount_images {
             let semaphore_finished =
                 unsafe { device.logical.create_semaphore(&semaphore_info, None)? };
-
-            let fence = unsafe { device.logical.create_fence(&fence_info, None)? };
-
-            images_available.push(semaphore_available);
-            rendering_finished.push(semaphore_finished);
-            fences.push(fence);
+            self.rendering_finished.push(semaphore_finished);
         }
-
-        Ok(Self {
-            raw: swapchain,
-            loader: swapchain_loader,
-            images: swapchain_images,
-            images_view: swapchain_images_views,
-            format,
-            color_space,
-            framebuffers: Vec::new(),
-            extent: swapchain_extent,
-            images_available,
-            rendering_finished,
-            amount_images,
-            fences,
-            current_image: 0,
-        })
+        Ok(())
     }
 
     pub fn create_framebuffers(
@@ -195,8 +243,10 @@ impl Swapchain {
         device: &Device,
         renderpass: &RenderPass,
     ) -> Result<(), Box<dyn Error>> {
+        let depth = self.depth.as_ref().ok_or("swapchain has no depth image")?;
+
         for image in self.images_view.iter() {
-            let image_view = [*image];
+            let image_view = [*image, depth.view];
             let framebuffer_info = vk::FramebufferCreateInfo::default()
                 .render_pass(renderpass.raw)
                 .attachments(&image_view)
@@ -212,27 +262,102 @@ impl Swapchain {
         Ok(())
     }
 
-    pub fn clean(&self, device: &Device) {
-        unsafe {
-            for fence in self.fences.iter() {
-                device.logical.destroy_fence(*fence, None);
-            }
+    /// Rebuilds everything that depends on the surface extent. Returns false and keeps what
+    /// exists when the surface has no area, it can lag behind the window being minimized
+    pub fn recreate(
+        &mut self,
+        window: &Window,
+        surface: &Surface,
+        device: &Device,
+        allocator: &mut Allocator,
+        renderpass: &RenderPass,
+    ) -> Result<bool, Box<dyn Error>> {
+        let capabilities = unsafe {
+            surface
+                .loader
+                .get_physical_device_surface_capabilities(device.physical, surface.raw)?
+        };
 
-            for semaphore in self.images_available.iter() {
-                device.logical.destroy_semaphore(*semaphore, None);
+        let extent = surface_extent(window, &capabilities);
+
+        if extent.width == 0 || extent.height == 0 {
+            return Ok(false);
+        }
+
+        unsafe { device.logical.device_wait_idle()? };
+
+        self.clean_images(device, allocator);
+        self.create(window, surface, device, allocator)?;
+        self.create_framebuffers(device, renderpass)?;
+        Ok(true)
+    }
+
+    fn clean_images(&mut self, device: &Device, allocator: &mut Allocator) {
+        unsafe {
+            for framebuffer in self.framebuffers.drain(..) {
+                device.logical.destroy_framebuffer(framebuffer, None);
             }
-            for semaphore in self.rendering_finished.iter() {
-                device.logical.destroy_semaphore(*semaphore, None);
+            for image in self.images_view.drain(..) {
+                device.logical.destroy_image_view(image, None);
             }
+        }
+        if let Some(depth) = self.depth.take() {
+            depth.clean(device, allocator);
+        }
+    }
 
-            for framebuffer in self.framebuffers.iter() {
-                device.logical.destroy_framebuffer(*framebuffer, None);
-            }
-            for image in self.images_view.iter() {
-                device.logical.destroy_image_view(*image, None);
+    fn clean_sync_objects(&mut self, device: &Device) {
+        unsafe {
+            for semaphore in self.rendering_finished.drain(..) {
+                device.logical.destroy_semaphore(semaphore, None);
             }
+        }
+    }
 
-            self.loader.destroy_swapchain(self.raw, None);
-        };
+    pub fn clean(&mut self, device: &Device, allocator: &mut Allocator) {
+        self.clean_sync_objects(device);
+        self.clean_images(device, allocator);
+
+        unsafe { self.loader.destroy_swapchain(self.raw, None) };
+    }
+}
+
+impl PresentTarget for Swapchain {
+    fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    fn final_layout(&self) -> vk::ImageLayout {
+        vk::ImageLayout::PRESENT_SRC_KHR
+    }
+
+    fn framebuffer(&self, image_index: usize) -> vk::Framebuffer {
+        self.framebuffers[image_index]
+    }
+
+    fn frames_in_flight(&self) -> usize {
+        FRAMES_IN_FLIGHT
+    }
+}
+
+/// Size the images must have, the surface decides unless it follows the window
+fn surface_extent(window: &Window, capabilities: &vk::SurfaceCapabilitiesKHR) -> vk::Extent2D {
+    if capabilities.current_extent.width != u32::MAX {
+        return capabilities.current_extent;
+    }
+
+    vk::Extent2D {
+        width: window.inner_size().width.clamp(
+            capabilities.min_image_extent.width,
+            capabilities.max_image_extent.width,
+        ),
+        height: window.inner_size().height.clamp(
+            capabilities.min_image_extent.height,
+            capabilities.max_image_extent.height,
+        ),
     }
 }
@@ -0,0 +1,27 @@
+use ash::vk;
+
+use crate::scop::vulkan::device::Device;
+
+/// Where rendered frames end up, the window through the swapchain or an offscreen image
+pub trait PresentTarget {
+    fn format(&self) -> vk::Format;
+
+    fn extent(&self) -> vk::Extent2D;
+
+    /// Layout the color attachment is left in by the render pass
+    fn final_layout(&self) -> vk::ImageLayout;
+
+    fn framebuffer(&self, image_index: usize) -> vk::Framebuffer;
+
+    /// How many frames the cpu may record ahead of the gpu, each has its own resources
+    fn frames_in_flight(&self) -> usize;
+
+    /// Recorded after the render pass, in the same command buffer
+    fn record_after(
+        &self,
+        _device: &Device,
+        _command_buffer: vk::CommandBuffer,
+        _image_index: usize,
+    ) {
+    }
+}
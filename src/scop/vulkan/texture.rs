@@ -0,0 +1,91 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::image::Image;
+
+use crate::scop::vulkan::memory::Allocator;
+
+use crate::scop::vulkan::upload::Upload;
+
+use crate::scop::texture::Texture;
+
+/// Texture pixels are authored in sRGB, sampling converts them to linear
+const TEXTURE_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+pub struct TextureBuffer {
+    pub image: Image,
+    pub sampler: vk::Sampler,
+}
+
+impl TextureBuffer {
+    pub fn new(
+        instance: &ash::Instance,
+        device: &Device,
+        allocator: &mut Allocator,
+        upload: &mut Upload,
+        texture: &Texture,
+    ) -> Result<Self, Box<dyn Error>> {
+        let properties = unsafe {
+            instance.get_physical_device_format_properties(device.physical, TEXTURE_FORMAT)
+        };
+
+        // mips are blitted with a linear filter, without support the texture keeps one level
+        let mip_levels = if properties
+            .optimal_tiling_features
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
+        {
+            Image::mip_chain_length(vk::Extent2D {
+                width: texture.width,
+                height: texture.height,
+            })
+        } else {
+            1
+        };
+
+        let image = upload.image(device, allocator, texture, TEXTURE_FORMAT, mip_levels)?;
+
+        let anisotropy = device.features.sampler_anisotropy == vk::TRUE;
+
+        let sampler_info = vk::SamplerCreateInfo::default()
+            .mag_filter(vk::Filter::LINEAR)
+            .min_filter(vk::Filter::LINEAR)
+            .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+            .address_mode_u(vk::SamplerAddressMode::REPEAT)
+            .address_mode_v(vk::SamplerAddressMode::REPEAT)
+            .address_mode_w(vk::SamplerAddressMode::REPEAT)
+            .anisotropy_enable(anisotropy)
+            .max_anisotropy(if anisotropy {
+                device.properties.limits.max_sampler_anisotropy
+            } else {
+                1.0
+            })
+            .min_lod(0.0)
+            .max_lod(mip_levels as f32)
+            .border_color(vk::BorderColor::INT_OPAQUE_BLACK);
+
+        let sampler = match unsafe { device.logical.create_sampler(&sampler_info, None) } {
+            Ok(sampler) => sampler,
+            Err(err) => {
+                image.clean(device, allocator);
+                return Err(err.into());
+            }
+        };
+
+        Ok(Self { image, sampler })
+    }
+
+    pub fn descriptor_info(&self) -> vk::DescriptorImageInfo {
+        vk::DescriptorImageInfo::default()
+            .sampler(self.sampler)
+            .image_view(self.image.view)
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+    }
+
+    pub fn clean(&self, device: &Device, allocator: &mut Allocator) {
+        unsafe { device.logical.destroy_sampler(self.sampler, None) };
+        self.image.clean(device, allocator);
+    }
+}
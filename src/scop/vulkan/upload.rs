@@ -0,0 +1,571 @@
+use ash::vk;
+
+use std::error::Error;
+
+use crate::scop::vulkan::buffer::Buffer;
+
+use crate::scop::vulkan::device::Device;
+
+use crate::scop::vulkan::image::Image;
+
+use crate::scop::vulkan::memory::{Allocator, MemoryUsage};
+
+use crate::scop::vulkan::pools::Pools;
+
+use crate::scop::texture::Texture;
+
+/// Batches staging copies on the transfer queue, then hands the resources over to the
+/// graphic queue family
+pub struct Upload {
+    staging: Vec<Buffer>,
+    transfer: vk::CommandBuffer,
+    /// Records the ownership acquire barriers, only needed when the families differ
+    graphic: Option<vk::CommandBuffer>,
+}
+
+impl Upload {
+    pub fn begin(device: &Device, pools: &Pools) -> Result<Self, Box<dyn Error>> {
+        let begin_info = vk::CommandBufferBeginInfo::default()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+        let transfer = allocate(device, pools.transfer)?;
+
+        unsafe { device.logical.begin_command_buffer(transfer, &begin_info)? };
+
+        let graphic = if device.transfer_index != device.graphic_index {
+            let graphic = allocate(device, pools.graphic)?;
+
+            unsafe { device.logical.begin_command_buffer(graphic, &begin_info)? };
+
+            Some(graphic)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            staging: Vec::new(),
+            transfer,
+            graphic,
+        })
+    }
+
+    /// Records the copy of `data` into a new device local buffer, usable once submitted
+    pub fn buffer<T: Copy>(
+        &mut self,
+        device: &Device,
+        allocator: &mut Allocator,
+        data: &[T],
+        usage: vk::BufferUsageFlags,
+    ) -> Result<Buffer, Box<dyn Error>> {
+        let size = size_of_val(data) as vk::DeviceSize;
+
+        let staging = stage(device, allocator, data)?;
+
+        let buffer = match Buffer::new(
+            device,
+            allocator,
+            size,
+            usage | vk::BufferUsageFlags::TRANSFER_DST,
+            MemoryUsage::GpuOnly,
+        ) {
+            Ok(buffer) => buffer,
+            Err(err) => {
+                staging.clean(device, allocator);
+                return Err(err);
+            }
+        };
+
+        let regions = [vk::BufferCopy::default().size(size)];
+
+        unsafe {
+            device
+                .logical
+                .cmd_copy_buffer(self.transfer, staging.raw, buffer.raw, &regions)
+        };
+
+        let (dst_stage, dst_access) = buffer_destination(usage);
+
+        let barrier = vk::BufferMemoryBarrier::default()
+            .buffer(buffer.raw)
+            .offset(0)
+            .size(vk::WHOLE_SIZE)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE);
+
+        match self.graphic {
+            Some(graphic) => {
+                let barrier = barrier
+                    .src_queue_family_index(device.transfer_index)
+                    .dst_queue_family_index(device.graphic_index);
+
+                // the release ignores the destination scope, the acquire the source one
+                unsafe {
+                    device.logical.cmd_pipeline_barrier(
+                        self.transfer,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[barrier],
+                        &[],
+                    );
+
+                    device.logical.cmd_pipeline_barrier(
+                        graphic,
+                        vk::PipelineStageFlags::ALL_COMMANDS,
+                        dst_stage,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[barrier
+                            .src_access_mask(vk::AccessFlags::empty())
+                            .dst_access_mask(dst_access)],
+                        &[],
+                    );
+                }
+            }
+            None => unsafe {
+                device.logical.cmd_pipeline_barrier(
+                    self.transfer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    dst_stage,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[barrier
+                        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .dst_access_mask(dst_access)],
+                    &[],
+                );
+            },
+        }
+
+        self.staging.push(staging);
+        Ok(buffer)
+    }
+
+    /// Records the copy of `texture` into a new sampled image, left in
+    /// `SHADER_READ_ONLY_OPTIMAL` layout for the fragment shader. With more than one mip level
+    /// the smaller levels are blitted from the first one on the graphic queue, the format must
+    /// support linear filtering
+    pub fn image(
+        &mut self,
+        device: &Device,
+        allocator: &mut Allocator,
+        texture: &Texture,
+        format: vk::Format,
+        mip_levels: u32,
+    ) -> Result<Image, Box<dyn Error>> {
+        let staging = stage(device, allocator, &texture.pixels)?;
+
+        let extent = vk::Extent2D {
+            width: texture.width,
+            height: texture.height,
+        };
+
+        let mut usage = vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST;
+
+        if mip_levels > 1 {
+            usage |= vk::ImageUsageFlags::TRANSFER_SRC;
+        }
+
+        let image = match Image::new(
+            device,
+            allocator,
+            extent,
+            format,
+            mip_levels,
+            usage,
+            vk::ImageAspectFlags::COLOR,
+        ) {
+            Ok(image) => image,
+            Err(err) => {
+                staging.clean(device, allocator);
+                return Err(err);
+            }
+        };
+
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .base_mip_level(0)
+            .level_count(mip_levels)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        let barrier = vk::ImageMemoryBarrier::default()
+            .image(image.raw)
+            .subresource_range(subresource_range)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED);
+
+        let regions = [vk::BufferImageCopy::default()
+            .image_subresource(
+                vk::ImageSubresourceLayers::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .mip_level(0)
+                    .base_array_layer(0)
+                    .layer_count(1),
+            )
+            .image_extent(vk::Extent3D {
+                width: texture.width,
+                height: texture.height,
+                depth: 1,
+            })];
+
+        unsafe {
+            device.logical.cmd_pipeline_barrier(
+                self.transfer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier
+                    .old_layout(vk::ImageLayout::UNDEFINED)
+                    .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                    .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)],
+            );
+
+            device.logical.cmd_copy_buffer_to_image(
+                self.transfer,
+                staging.raw,
+                image.raw,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &regions,
+            );
+        }
+
+        // the mip blits still need the image as a transfer destination
+        let (layout, dst_stage, dst_access) = if mip_levels > 1 {
+            (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ | vk::AccessFlags::TRANSFER_WRITE,
+            )
+        } else {
+            (
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::AccessFlags::SHADER_READ,
+            )
+        };
+
+        // the layout transition happens once, as part of the ownership transfer if any
+        let barrier = barrier
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(layout)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE);
+
+        match self.graphic {
+            Some(graphic) => {
+                let barrier = barrier
+                    .src_queue_family_index(device.transfer_index)
+                    .dst_queue_family_index(device.graphic_index);
+
+                unsafe {
+                    device.logical.cmd_pipeline_barrier(
+                        self.transfer,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[],
+                        &[barrier],
+                    );
+
+                    device.logical.cmd_pipeline_barrier(
+                        graphic,
+                        vk::PipelineStageFlags::ALL_COMMANDS,
+                        dst_stage,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[],
+                        &[barrier
+                            .src_access_mask(vk::AccessFlags::empty())
+                            .dst_access_mask(dst_access)],
+                    );
+                }
+            }
+            None => unsafe {
+                device.logical.cmd_pipeline_barrier(
+                    self.transfer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    dst_stage,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[barrier.dst_access_mask(dst_access)],
+                );
+            },
+        }
+
+        if mip_levels > 1 {
+            self.generate_mipmaps(device, image.raw, extent, mip_levels);
+        }
+
+        self.staging.push(staging);
+        Ok(image)
+    }
+
+    /// Halves each level into the next one, every level ends in `SHADER_READ_ONLY_OPTIMAL`
+    fn generate_mipmaps(
+        &self,
+        device: &Device,
+        image: vk::Image,
+        extent: vk::Extent2D,
+        mip_levels: u32,
+    ) {
+        // blits need a graphic queue, which the transfer one is when the families match
+        let command_buffer = self.graphic.unwrap_or(self.transfer);
+
+        let barrier = |level: u32| {
+            vk::ImageMemoryBarrier::default()
+                .image(image)
+                .subresource_range(
+                    vk::ImageSubresourceRange::default()
+                        .aspect_mask(vk::ImageAspectFlags::COLOR)
+                        .base_mip_level(level)
+                        .level_count(1)
+                        .base_array_layer(0)
+                        .layer_count(1),
+                )
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        };
+
+        let layers = |level: u32| {
+            vk::ImageSubresourceLayers::default()
+                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                .mip_level(level)
+                .base_array_layer(0)
+                .layer_count(1)
+        };
+
+        let corner = |level: u32| vk::Offset3D {
+            x: (extent.width >> level).max(1) as i32,
+            y: (extent.height >> level).max(1) as i32,
+            z: 1,
+        };
+
+        for level in 1..mip_levels {
+            let blit = vk::ImageBlit::default()
+                .src_subresource(layers(level - 1))
+                .src_offsets([vk::Offset3D::default(), corner(level - 1)])
+                .dst_subresource(layers(level))
+                .dst_offsets([vk::Offset3D::default(), corner(level)]);
+
+            unsafe {
+                device.logical.cmd_pipeline_barrier(
+                    command_buffer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[barrier(level - 1)
+                        .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                        .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                        .dst_access_mask(vk::AccessFlags::TRANSFER_READ)],
+                );
+
+                device.logical.cmd_blit_image(
+                    command_buffer,
+                    image,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[blit],
+                    vk::Filter::LINEAR,
+                );
+
+                device.logical.cmd_pipeline_barrier(
+                    command_buffer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::FRAGMENT_SHADER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[barrier(level - 1)
+                        .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                        .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                        .src_access_mask(vk::AccessFlags::TRANSFER_READ)
+                        .dst_access_mask(vk::AccessFlags::SHADER_READ)],
+                );
+            }
+        }
+
+        // the last level was only written to
+        unsafe {
+            device.logical.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier(mip_levels - 1)
+                    .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                    .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                    .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                    .dst_access_mask(vk::AccessFlags::SHADER_READ)],
+            );
+        }
+    }
+
+    /// Runs the recorded copies and waits for them, the staging memory is released afterwards
+    pub fn submit(
+        self,
+        device: &Device,
+        allocator: &mut Allocator,
+        pools: &Pools,
+    ) -> Result<(), Box<dyn Error>> {
+        let result = self.execute(device);
+
+        self.cancel(device, allocator, pools);
+
+        result
+    }
+
+    /// Releases the command buffers and the staging memory without running the copies
+    pub fn cancel(self, device: &Device, allocator: &mut Allocator, pools: &Pools) {
+        unsafe {
+            device
+                .logical
+                .free_command_buffers(pools.transfer, &[self.transfer]);
+
+            if let Some(graphic) = self.graphic {
+                device
+                    .logical
+                    .free_command_buffers(pools.graphic, &[graphic]);
+            }
+        }
+
+        for staging in self.staging.iter() {
+            staging.clean(device, allocator);
+        }
+    }
+
+    fn execute(&self, device: &Device) -> Result<(), Box<dyn Error>> {
+        let transfer_buffers = [self.transfer];
+
+        unsafe { device.logical.end_command_buffer(self.transfer)? };
+
+        let Some(graphic) = self.graphic else {
+            let submit_info = [vk::SubmitInfo::default().command_buffers(&transfer_buffers)];
+
+            unsafe {
+                device.logical.queue_submit(
+                    device.transfer_queue,
+                    &submit_info,
+                    vk::Fence::null(),
+                )?;
+                device.logical.queue_wait_idle(device.transfer_queue)?;
+            }
+            return Ok(());
+        };
+
+        let graphic_buffers = [graphic];
+
+        unsafe { device.logical.end_command_buffer(graphic)? };
+
+        let semaphore = unsafe {
+            device
+                .logical
+                .create_semaphore(&vk::SemaphoreCreateInfo::default(), None)?
+        };
+
+        let semaphores = [semaphore];
+
+        let waiting_stages = [vk::PipelineStageFlags::ALL_COMMANDS];
+
+        let transfer_submit = [vk::SubmitInfo::default()
+            .command_buffers(&transfer_buffers)
+            .signal_semaphores(&semaphores)];
+
+        let graphic_submit = [vk::SubmitInfo::default()
+            .wait_semaphores(&semaphores)
+            .wait_dst_stage_mask(&waiting_stages)
+            .command_buffers(&graphic_buffers)];
+
+        let result = unsafe {
+            device
+                .logical
+                .queue_submit(device.transfer_queue, &transfer_submit, vk::Fence::null())
+                .and_then(|_| {
+                    device.logical.queue_submit(
+                        device.graphic_queue,
+                        &graphic_submit,
+                        vk::Fence::null(),
+                    )
+                })
+                .and_then(|_| device.logical.queue_wait_idle(device.graphic_queue))
+        };
+
+        // a failed submit may leave the other one pending, the semaphore is only destroyed
+        // once the device is idle. If even that fails the device is lost and nothing uses it
+        if result.is_err() {
+            let _ = unsafe { device.logical.device_wait_idle() };
+        }
+
+        unsafe { device.logical.destroy_semaphore(semaphore, None) };
+
+        Ok(result?)
+    }
+}
+
+/// Host visible copy of `data`, the source of a transfer
+fn stage<T: Copy>(
+    device: &Device,
+    allocator: &mut Allocator,
+    data: &[T],
+) -> Result<Buffer, Box<dyn Error>> {
+    let staging = Buffer::new(
+        device,
+        allocator,
+        size_of_val(data) as vk::DeviceSize,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        MemoryUsage::CpuToGpu,
+    )?;
+
+    if let Err(err) = staging.write(data) {
+        staging.clean(device, allocator);
+        return Err(err);
+    }
+    Ok(staging)
+}
+
+fn allocate(device: &Device, pool: vk::CommandPool) -> Result<vk::CommandBuffer, Box<dyn Error>> {
+    let allocate_info = vk::CommandBufferAllocateInfo::default()
+        .command_pool(pool)
+        .command_buffer_count(1);
+
+    Ok(unsafe { device.logical.allocate_command_buffers(&allocate_info)? }[0])
+}
+
+/// Stages and accesses that first read a buffer with the given usage
+fn buffer_destination(usage: vk::BufferUsageFlags) -> (vk::PipelineStageFlags, vk::AccessFlags) {
+    let mut stage = vk::PipelineStageFlags::empty();
+
+    let mut access = vk::AccessFlags::empty();
+
+    if usage.contains(vk::BufferUsageFlags::VERTEX_BUFFER) {
+        stage |= vk::PipelineStageFlags::VERTEX_INPUT;
+        access |= vk::AccessFlags::VERTEX_ATTRIBUTE_READ;
+    }
+
+    if usage.contains(vk::BufferUsageFlags::INDEX_BUFFER) {
+        stage |= vk::PipelineStageFlags::VERTEX_INPUT;
+        access |= vk::AccessFlags::INDEX_READ;
+    }
+
+    if usage.intersects(vk::BufferUsageFlags::UNIFORM_BUFFER | vk::BufferUsageFlags::STORAGE_BUFFER)
+    {
+        stage |= vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER;
+        access |= vk::AccessFlags::SHADER_READ;
+    }
+
+    if stage.is_empty() {
+        stage = vk::PipelineStageFlags::ALL_COMMANDS;
+        access = vk::AccessFlags::MEMORY_READ;
+    }
+    (stage, access)
+}
@@ -0,0 +1,236 @@
+//! Renders the fixtures offscreen and compares them with the reference images in
+//! `tests/golden`. They need a vulkan device, so they are ignored by default and meant for a
+//! software driver so results do not depend on the gpu:
+//! `VK_DRIVER_FILES=/usr/share/vulkan/icd.d/lvp_icd.x86_64.json cargo test --test golden -- --ignored`.
+//!
+//! `SCOP_BLESS=1` records the references from the current renders after an intended change,
+//! otherwise a missing reference or device fails the test.
+
+use std::path::{Path, PathBuf};
+
+use scop::Headless;
+
+use scop::config::Config;
+
+use scop::loader::load_obj;
+
+use scop::math::Mat4;
+
+use scop::scene::{Scene, Shading};
+
+use scop::texture::{load_texture, save_texture, Texture, Textures};
+
+const WIDTH: u32 = 160;
+
+const HEIGHT: u32 = 120;
+
+/// Largest difference on any channel for two pixels to still match
+const TOLERANCE: u8 = 4;
+
+/// Share of pixels allowed to differ more, rasterization rules leave some freedom on edges
+const MAX_MISMATCH: f32 = 0.002;
+
+struct Case {
+    name: &'static str,
+    model: &'static str,
+    shading: Shading,
+    texture_blend: f32,
+    rotation: Mat4,
+}
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn reference(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.ppm"))
+}
+
+/// Where the render and the diff of a failed comparison are written
+fn failure(name: &str, kind: &str) -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.{kind}.png"))
+}
+
+fn render(case: &Case) -> Texture {
+    let args = [
+        "--width",
+        &WIDTH.to_string(),
+        "--height",
+        &HEIGHT.to_string(),
+        "--validation-panic",
+        fixture(case.model).to_str().expect("fixture path is utf-8"),
+    ]
+    .map(String::from);
+
+    let config = Config::from_args(args)
+        .expect("valid arguments")
+        .expect("not asking for help");
+
+    let mut mesh = load_obj(&config.model).expect("fixture loads");
+
+    if !mesh.has_normals {
+        mesh.generate_normals(config.crease_angle);
+    }
+
+    if !mesh.has_texcoords {
+        mesh.generate_texcoords(config.uv_mapping);
+    }
+
+    let textures = Textures::single(Texture::checkerboard(64, 4));
+
+    let mut headless = match Headless::new(&config, &mesh, &textures) {
+        Ok(headless) => headless,
+        Err(err) => panic!("{}: no usable vulkan device: {err}", case.name),
+    };
+
+    let scene = Scene {
+        model: case.rotation * mesh.bounds.fit(1.5),
+        shading: case.shading,
+        texture_blend: case.texture_blend,
+        ..Scene::default()
+    };
+
+    let pixels = headless.render(&scene).expect("frame renders");
+
+    let (width, height) = headless.size();
+
+    Texture {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// Actual pixels where they match, dimmed, and red where they do not
+fn diff_image(actual: &Texture, expected: &Texture, mismatches: &[bool]) -> Texture {
+    let pixels = actual
+        .pixels
+        .chunks_exact(4)
+        .zip(mismatches)
+        .flat_map(|(pixel, mismatch)| {
+            if *mismatch {
+                [0xff, 0x00, 0x00, 0xff]
+            } else {
+                [pixel[0] / 3, pixel[1] / 3, pixel[2] / 3, 0xff]
+            }
+        })
+        .collect();
+
+    Texture {
+        width: expected.width,
+        height: expected.height,
+        pixels,
+    }
+}
+
+fn check(case: Case) {
+    let actual = render(&case);
+
+    let path = reference(case.name);
+
+    if std::env::var_os("SCOP_BLESS").is_some() {
+        std::fs::create_dir_all(path.parent().expect("reference has a directory"))
+            .expect("reference directory is writable");
+        save_texture(&path, &actual).expect("reference is writable");
+        eprintln!("{}: recorded {}", case.name, path.display());
+        return;
+    }
+
+    assert!(
+        path.exists(),
+        "{}: missing reference {}, record it with SCOP_BLESS=1",
+        case.name,
+        path.display()
+    );
+
+    let expected = load_texture(&path).expect("reference loads");
+
+    assert_eq!(
+        (actual.width, actual.height),
+        (expected.width, expected.height),
+        "{}: size differs from the reference",
+        case.name
+    );
+
+    // references are ppm files, alpha is not compared
+    let mismatches: Vec<bool> = actual
+        .pixels
+        .chunks_exact(4)
+        .zip(expected.pixels.chunks_exact(4))
+        .map(|(a, b)| (0..3).any(|channel| a[channel].abs_diff(b[channel]) > TOLERANCE))
+        .collect();
+
+    let mismatched = mismatches.iter().filter(|mismatch| **mismatch).count();
+
+    let share = mismatched as f32 / mismatches.len() as f32;
+
+    if share > MAX_MISMATCH {
+        let actual_path = failure(case.name, "actual");
+        let diff_path = failure(case.name, "diff");
+
+        save_texture(&actual_path, &actual).expect("render is writable");
+        save_texture(&diff_path, &diff_image(&actual, &expected, &mismatches))
+            .expect("diff is writable");
+
+        panic!(
+            "{}: {mismatched} pixels differ from {} ({:.2}%), see {} and {}",
+            case.name,
+            path.display(),
+            share * 100.0,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs a vulkan device, run with --ignored"]
+fn cube_with_generated_normals() {
+    check(Case {
+        name: "cube",
+        model: "cube.obj",
+        shading: Shading::Grey,
+        texture_blend: 0.0,
+        rotation: Mat4::rotation_x(0.4) * Mat4::rotation_y(0.6),
+    });
+}
+
+#[test]
+#[ignore = "needs a vulkan device, run with --ignored"]
+fn cube_palette_faces() {
+    check(Case {
+        name: "cube_palette",
+        model: "cube.obj",
+        shading: Shading::Palette,
+        texture_blend: 0.0,
+        rotation: Mat4::rotation_x(-0.3) * Mat4::rotation_y(2.2),
+    });
+}
+
+#[test]
+#[ignore = "needs a vulkan device, run with --ignored"]
+fn textured_quad() {
+    check(Case {
+        name: "quad",
+        model: "quad.obj",
+        shading: Shading::Material,
+        texture_blend: 1.0,
+        rotation: Mat4::rotation_x(-0.5),
+    });
+}
+
+#[test]
+#[ignore = "needs a vulkan device, run with --ignored"]
+fn materials_per_group() {
+    check(Case {
+        name: "materials",
+        model: "materials.obj",
+        shading: Shading::Material,
+        texture_blend: 0.0,
+        rotation: Mat4::rotation_x(0.5) * Mat4::rotation_y(0.8),
+    });
+}